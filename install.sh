@@ -1,2 +1,2 @@
-cargo run --release
+cargo run --release -- modern.sfff font-main/main.svg font-main/meta.json --vfont vfont
 cp vfont/* ../score2svg/src/vfont
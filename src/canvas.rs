@@ -0,0 +1,197 @@
+//! Canvas drawing command output, for the HTML canvas renderer to draw
+//! glyphs without parsing SVG paths.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use sfff::STAVE_SPACE;
+use svgdom::Path;
+
+use crate::outline;
+
+/// Commands, each followed by its arguments: the points of the matching
+/// `CanvasRenderingContext2D` method.
+const COMMANDS: [(&str, &str, usize); 5] = [
+    ("MOVE_TO", "moveTo", 1),
+    ("LINE_TO", "lineTo", 1),
+    ("BEZIER_CURVE_TO", "bezierCurveTo", 3),
+    ("QUADRATIC_CURVE_TO", "quadraticCurveTo", 2),
+    ("CLOSE_PATH", "closePath", 0),
+];
+const MOVE_TO: usize = 0;
+const LINE_TO: usize = 1;
+const BEZIER_CURVE_TO: usize = 2;
+const QUADRATIC_CURVE_TO: usize = 3;
+const CLOSE_PATH: usize = 4;
+
+/// Write `canvas.js` and its type declarations `canvas.d.ts` into `dir`: each
+/// glyph as a flat array of drawing commands (named after its `Glyph`
+/// variant, in lower camel case), and a `draw` function that replays them on
+/// a canvas context or `Path2D`.  `glyphs` are the variant names, SMuFL
+/// codepoints and paths of the glyphs, and `font` the outlines they were made
+/// from.
+pub fn write(
+    dir: &str,
+    font_name: &str,
+    glyphs: &[(&str, u16, String)],
+    font: &HashMap<u16, Path>,
+) {
+    let mut js = String::new();
+    let mut ts = String::new();
+    for out in [&mut js, &mut ts].iter_mut() {
+        writeln!(out, "// {} ScoreFall font, generated by scorefall-font.", font_name).unwrap();
+        writeln!(out, "// Don't edit; regenerate it instead.").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "/** Units per stave space. */").unwrap();
+    }
+    writeln!(js, "export const STAVE_SPACE = {};", STAVE_SPACE).unwrap();
+    writeln!(ts, "export declare const STAVE_SPACE: {};", STAVE_SPACE).unwrap();
+
+    // Commands, and how to replay them.
+    for out in [&mut js, &mut ts].iter_mut() {
+        writeln!(out).unwrap();
+        writeln!(out, "// Drawing commands; each is followed by its arguments.").unwrap();
+    }
+    for (i, (constant, method, points)) in COMMANDS.iter().enumerate() {
+        writeln!(js, "/** `{}`, with {} arguments. */", method, points * 2).unwrap();
+        writeln!(js, "export const {} = {};", constant, i).unwrap();
+        writeln!(ts, "/** `{}`, with {} arguments. */", method, points * 2).unwrap();
+        writeln!(ts, "export declare const {}: {};", constant, i).unwrap();
+    }
+    writeln!(js).unwrap();
+    writeln!(js, "/** Draw the commands of a glyph onto a canvas context or `Path2D`. */").unwrap();
+    writeln!(js, "export function draw(ctx, commands) {{").unwrap();
+    writeln!(js, "  const c = commands;").unwrap();
+    writeln!(js, "  for (let i = 0; i < c.length; ) {{").unwrap();
+    writeln!(js, "    switch (c[i++]) {{").unwrap();
+    for (constant, method, points) in COMMANDS.iter() {
+        let args: Vec<&str> = (0..points * 2).map(|_| "c[i++]").collect();
+        let args = args.join(", ");
+        writeln!(js, "      case {}: ctx.{}({}); break;", constant, method, args).unwrap();
+    }
+    writeln!(js, "      default: throw new Error(\"Bad drawing command\");").unwrap();
+    writeln!(js, "    }}").unwrap();
+    writeln!(js, "  }}").unwrap();
+    writeln!(js, "}}").unwrap();
+    writeln!(ts).unwrap();
+    writeln!(ts, "/** Draw the commands of a glyph onto a canvas context or `Path2D`. */").unwrap();
+    writeln!(ts, "export declare function draw(").unwrap();
+    writeln!(ts, "  ctx: CanvasRenderingContext2D | Path2D,").unwrap();
+    writeln!(ts, "  commands: readonly number[],").unwrap();
+    writeln!(ts, "): void;").unwrap();
+
+    // Glyphs.
+    for (name, cp, _) in glyphs {
+        let id = crate::lower_camel(name);
+        for out in [&mut js, &mut ts].iter_mut() {
+            writeln!(out).unwrap();
+            writeln!(out, "/** `{}` (U+{:04X}), with y down. */", name, cp).unwrap();
+        }
+        let commands = font.get(cp).map(commands).unwrap_or_default();
+        let commands: Vec<String> = commands.iter().map(i32::to_string).collect();
+        writeln!(js, "export const {} = [{}];", id, commands.join(",")).unwrap();
+        writeln!(ts, "export declare const {}: readonly number[];", id).unwrap();
+    }
+
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(format!("{}/canvas.js", dir), js).unwrap();
+    std::fs::write(format!("{}/canvas.d.ts", dir), ts).unwrap();
+}
+
+/// Drawing commands of an outline, in whole units with the y axis down (as
+/// paths are written).  Arcs become cubic curves.
+fn commands(path: &Path) -> Vec<i32> {
+    use svgdom::PathSegment::*;
+
+    let mut out = Vec::new();
+    let (mut current, mut start) = ((0.0, 0.0), (0.0, 0.0));
+    for segment in outline::simplify(path).iter() {
+        match *segment {
+            MoveTo { x, y, .. } => {
+                push(&mut out, MOVE_TO, &[(x, y)]);
+                start = (x, y);
+                current = start;
+            }
+            LineTo { x, y, .. } => {
+                push(&mut out, LINE_TO, &[(x, y)]);
+                current = (x, y);
+            }
+            CurveTo { x1, y1, x2, y2, x, y, .. } => {
+                push(&mut out, BEZIER_CURVE_TO, &[(x1, y1), (x2, y2), (x, y)]);
+                current = (x, y);
+            }
+            Quadratic { x1, y1, x, y, .. } => {
+                push(&mut out, QUADRATIC_CURVE_TO, &[(x1, y1), (x, y)]);
+                current = (x, y);
+            }
+            EllipticalArc { rx, ry, x_axis_rotation, large_arc, sweep, x, y, .. } => {
+                let to = (x, y);
+                let curves =
+                    outline::arc_to_curves(current, rx, ry, x_axis_rotation, large_arc, sweep, to);
+                for curve in curves.iter() {
+                    push(&mut out, BEZIER_CURVE_TO, curve);
+                }
+                current = to;
+            }
+            ClosePath { .. } => {
+                push(&mut out, CLOSE_PATH, &[]);
+                current = start;
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn push(out: &mut Vec<i32>, command: usize, points: &[(f64, f64)]) {
+    out.push(command as i32);
+    for (x, y) in points {
+        out.push(x.round() as i32);
+        out.push((-y).round() as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::temp_file;
+
+    #[test]
+    fn commands_with_y_down() {
+        let path = "M0 10h100Q150 10 150 60C150 100 100 110 50 110Z".parse().unwrap();
+        let expected = "0,0,-10,1,100,-10,3,150,-10,150,-60,2,150,-100,100,-110,50,-110,4";
+        let drawn: Vec<String> = commands(&path).iter().map(i32::to_string).collect();
+        assert_eq!(drawn.join(","), expected);
+
+        // Arcs become cubic curves ending where the arc does.
+        let arc = commands(&"M0 10A50 50 0 0 1 100 10".parse().unwrap());
+        assert_eq!(&arc[..3], &[0, 0, -10]);
+        assert!(arc[3..].chunks(7).all(|curve| curve.len() == 7 && curve[0] == 2));
+        assert_eq!(&arc[arc.len() - 2..], &[100, -10]);
+
+        let dir = temp_file("canvas");
+        let mut font = HashMap::new();
+        font.insert(0xE0A4, path);
+        let glyphs = [
+            ("NoteheadFill", 0xE0A4, String::new()),
+            ("Space", 0xE000, String::new()),
+        ];
+        write(&dir, "Test", &glyphs, &font);
+        let js = std::fs::read_to_string(format!("{}/canvas.js", dir)).unwrap();
+        let ts = std::fs::read_to_string(format!("{}/canvas.d.ts", dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(js.starts_with("// Test ScoreFall font, generated by scorefall-font.\n"));
+        assert!(js.contains("export const MOVE_TO = 0;\n"));
+        assert!(js.contains("export const CLOSE_PATH = 4;\n"));
+        assert!(js.contains(concat!(
+            "      case QUADRATIC_CURVE_TO: ",
+            "ctx.quadraticCurveTo(c[i++], c[i++], c[i++], c[i++]); break;\n",
+        )));
+        assert!(js.contains("      case CLOSE_PATH: ctx.closePath(); break;\n"));
+        assert!(js.contains(&format!("export const noteheadFill = [{}];\n", expected)));
+        assert!(js.contains("export const space = [];\n"));
+        assert!(ts.contains("export declare const BEZIER_CURVE_TO: 2;\n"));
+        assert!(ts.contains("export declare const noteheadFill: readonly number[];\n"));
+    }
+}
@@ -0,0 +1,38 @@
+//! SMuFL glyph classes, from `classes.json`.
+
+use std::collections::HashMap;
+
+use serde_json as json;
+
+/// Glyph names of the members of each SMuFL class.
+pub struct Classes(HashMap<String, Vec<String>>);
+
+impl Classes {
+    /// Load SMuFL's `classes.json`, exiting with a message on failure.
+    pub fn load(path: &str) -> Classes {
+        let data = std::fs::read_to_string(path).unwrap_or_else(|_| {
+            eprintln!("No such file: {}", path);
+            std::process::exit(1);
+        });
+        Classes::parse(path, &data)
+    }
+
+    /// Read classes from the text of the file `path`.
+    pub fn parse(path: &str, data: &str) -> Classes {
+        let classes = json::from_str(data).unwrap_or_else(|e| {
+            eprintln!("Failed to parse classes {}: {}", path, e);
+            std::process::exit(1);
+        });
+
+        Classes(classes)
+    }
+
+    /// Get the glyph names in a class, exiting with a message if there's no
+    /// such class.
+    pub fn members(&self, class: &str) -> &[String] {
+        self.0.get(class).map(|m| m.as_slice()).unwrap_or_else(|| {
+            eprintln!("No such SMuFL class: {}", class);
+            std::process::exit(1);
+        })
+    }
+}
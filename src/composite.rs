@@ -0,0 +1,170 @@
+//! Composite glyphs: arrangements of other glyphs (e.g. parenthesized
+//! accidentals or multi-digit tuplet numbers) flattened into a single path.
+//!
+//! Composites are defined in a JSON file, in order, so that a composite can
+//! use those defined before it:
+//!
+//! ```json
+//! [
+//!     {
+//!         "name": "accidentalFlatParens",
+//!         "codepoint": "U+F5D3",
+//!         "components": [
+//!             { "glyph": "accidentalParensLeft" },
+//!             { "glyph": "U+E260", "offset": [0.4, 0.0] },
+//!             { "glyph": "accidentalParensRight", "offset": [1.4, 0.0] }
+//!         ]
+//!     }
+//! ]
+//! ```
+//!
+//! Components are given by SMuFL name (which needs `--glyphnames`) or by
+//! codepoint.  Offsets are in stave spaces, with y up, and `scale` (default
+//! 1) scales the component about its origin before it's moved.
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json as json;
+use sfff::STAVE_SPACE;
+use svgdom::Path;
+
+use crate::glyphnames::{parse_codepoint, GlyphNames};
+use crate::outline;
+use crate::source::Layered;
+
+/// A component of a composite glyph.
+#[derive(Serialize, Deserialize)]
+pub struct Component {
+    /// SMuFL name or "U+XXXX" codepoint of the glyph.
+    pub glyph: String,
+    /// Position of the component's origin, in stave spaces.
+    #[serde(default)]
+    pub offset: [f32; 2],
+    /// Scale of the component.
+    #[serde(default = "one")]
+    pub scale: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// A composite glyph definition.
+#[derive(Serialize, Deserialize)]
+pub struct Composite {
+    /// Name of the composite glyph.
+    pub name: String,
+    /// "U+XXXX" codepoint the composite is provided at.
+    pub codepoint: String,
+    /// Glyphs to combine.
+    pub components: Vec<Component>,
+}
+
+/// Load composite glyph definitions, exiting with a message on failure.
+pub fn load(path: &str) -> Vec<Composite> {
+    let data = std::fs::read_to_string(path).unwrap_or_else(|_| {
+        eprintln!("No such file: {}", path);
+        std::process::exit(1);
+    });
+    json::from_str(&data).unwrap_or_else(|e| {
+        eprintln!("Failed to parse composites {}: {}", path, e);
+        std::process::exit(1);
+    })
+}
+
+/// Build each composite the font doesn't already have a glyph for, returning
+/// the name and codepoint of those built.
+pub fn build(
+    font: &mut Layered,
+    names: &GlyphNames,
+    composites: &[Composite],
+) -> Vec<(String, u16)> {
+    let mut built = Vec::new();
+    for composite in composites {
+        let codepoint = parse_codepoint(&composite.codepoint).unwrap_or_else(|| {
+            eprintln!("{}: bad codepoint {}", composite.name, composite.codepoint);
+            std::process::exit(1);
+        });
+        if font.glyphs.contains_key(&codepoint) {
+            continue;
+        }
+
+        match flatten(font, names, composite) {
+            Ok(path) => {
+                font.glyphs.insert(codepoint, path);
+                built.push((composite.name.clone(), codepoint));
+            }
+            Err(component) => {
+                println!("{}: no glyph for component {}", composite.name, component);
+            }
+        }
+    }
+    built
+}
+
+/// Flatten a composite into a single path, or return the first component
+/// that the font doesn't have.
+fn flatten(font: &Layered, names: &GlyphNames, composite: &Composite) -> Result<Path, String> {
+    let mut path = Path::new();
+    for component in composite.components.iter() {
+        let glyph = parse_codepoint(&component.glyph)
+            .filter(|_| component.glyph.starts_with("U+"))
+            .or_else(|| names.codepoint(&component.glyph))
+            .and_then(|cp| font.glyphs.get(&cp))
+            .ok_or_else(|| component.glyph.clone())?;
+        let dx = f64::from(component.offset[0]) * STAVE_SPACE as f64;
+        let dy = f64::from(component.offset[1]) * STAVE_SPACE as f64;
+        outline::append(
+            &mut path,
+            &outline::transform(glyph, f64::from(component.scale), dx, dy),
+        );
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{metadata_json, source};
+
+    #[test]
+    fn builds_in_order() {
+        let names = GlyphNames::parse(
+            "glyphnames.json",
+            r#"{"accidentalParensLeft": {"codepoint": "U+E26A", "description": ""}}"#,
+        );
+        let composites: Vec<Composite> = json::from_str(
+            r#"[
+                {"name": "flatLeft", "codepoint": "U+F5D0", "components": [
+                    {"glyph": "accidentalParensLeft"},
+                    {"glyph": "U+E260", "offset": [1.0, 0.5], "scale": 2}
+                ]},
+                {"name": "twice", "codepoint": "U+F5D1", "components": [
+                    {"glyph": "U+F5D0"},
+                    {"glyph": "U+F5D0", "offset": [4.0, 0.0]}
+                ]},
+                {"name": "provided", "codepoint": "U+E260", "components": [
+                    {"glyph": "U+E26A"}
+                ]},
+                {"name": "broken", "codepoint": "U+F5D2", "components": [
+                    {"glyph": "accidentalParensRight"}
+                ]}
+            ]"#,
+        )
+        .unwrap();
+        let glyphs = [
+            ("uniE26A", "", "M0 250L250 250L250 500Z"),
+            ("uniE260", "", "M0 250L250 250Z"),
+        ];
+        let mut font = Layered::new(source("base.svg", &glyphs, metadata_json("")));
+        let built = build(&mut font, &names, &composites);
+
+        let built: Vec<u16> = built.iter().map(|(_, cp)| *cp).collect();
+        assert_eq!(built, vec![0xF5D0, 0xF5D1]);
+        let ss = STAVE_SPACE as f64;
+        let bounds = outline::bounds(&font.glyphs[&0xF5D0]).unwrap();
+        assert_eq!(bounds, [0.0, ss, 3.0 * ss, 2.5 * ss]);
+        let bounds = outline::bounds(&font.glyphs[&0xF5D1]).unwrap();
+        assert_eq!(bounds, [0.0, ss, 7.0 * ss, 2.5 * ss]);
+        assert!(!font.glyphs.contains_key(&0xF5D2));
+    }
+}
@@ -0,0 +1,91 @@
+//! Compressed `.sfff` fonts, for clients that fetch fonts from servers that
+//! don't compress them over HTTP.
+//!
+//! A compressed font starts with the usual metadata, as
+//! `SfFontMetadata::write` writes it, but with `sffonts_version` set to
+//! `VERSION` so that readers can tell it apart.  After it, instead of the glyph
+//! path payload, comes the payload's length as a little-endian `u32`, then
+//! the payload compressed with raw deflate (RFC 1951).
+
+use std::io::Write;
+
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use sfff::SfFontMetadata;
+
+use crate::font_json::Metadata;
+
+/// `sffonts_version` of fonts with a compressed glyph path payload.
+pub const VERSION: u32 = 1;
+
+/// A font with `metadata` and the glyph path payload `glyph_paths`,
+/// compressed.
+pub fn font(metadata: &SfFontMetadata, glyph_paths: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    header(metadata).write(&mut out, "").unwrap();
+    out.extend_from_slice(&(glyph_paths.len() as u32).to_le_bytes());
+    let mut encoder = DeflateEncoder::new(out, Compression::best());
+    encoder.write_all(glyph_paths.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// The metadata of a compressed font.
+fn header(metadata: &SfFontMetadata) -> SfFontMetadata {
+    let mut header = Metadata::new(metadata);
+    header.sffonts_version = VERSION;
+    header.into_sfff()
+}
+
+/// Print the sizes of a font: as it is, compressed, and what a server sending
+/// it gzipped would transfer.
+pub fn report(font: &[u8], compressed: &[u8]) {
+    let percent = |size: usize| size as f64 * 100.0 / font.len() as f64;
+    let gzip = gzip_size(font);
+    println!("Raw:                 {:>7} bytes", font.len());
+    println!("Compressed:          {:>7} bytes ({:.1}%)", compressed.len(), percent(compressed.len()));
+    println!("Raw, gzip over HTTP: {:>7} bytes ({:.1}%)", gzip, percent(gzip));
+}
+
+/// Size of `data` gzipped at the level web servers usually use.
+fn gzip_size(data: &[u8]) -> usize {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(6));
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use serde_json as json;
+    use std::io::Read;
+
+    use crate::font_json::tests::sf_metadata;
+    use crate::source::tests::repo_file;
+
+    #[test]
+    fn only_paths_compressed() {
+        let metadata = sf_metadata();
+        // Any text will do for the payload, as long as it's long.
+        let glyph_paths = std::fs::read_to_string(repo_file("font-main/main.svg")).unwrap();
+        let font = font(&metadata, &glyph_paths);
+
+        let header = header(&metadata);
+        assert_eq!(header.sffonts_version, VERSION);
+        let mut expected = json::to_value(Metadata::new(&metadata)).unwrap();
+        expected["sffonts_version"] = json::json!(VERSION);
+        assert_eq!(json::to_value(Metadata::new(&header)).unwrap(), expected);
+
+        let mut start = Vec::new();
+        header.write(&mut start, "").unwrap();
+        assert!(font.starts_with(&start));
+        let length = &font[start.len()..start.len() + 4];
+        assert_eq!(length, (glyph_paths.len() as u32).to_le_bytes());
+        let compressed = &font[start.len() + 4..];
+        assert!(compressed.len() < glyph_paths.len() / 2);
+        let mut decompressed = String::new();
+        DeflateDecoder::new(compressed).read_to_string(&mut decompressed).unwrap();
+        assert!(decompressed == glyph_paths);
+        assert!(gzip_size(glyph_paths.as_bytes()) < glyph_paths.len());
+    }
+}
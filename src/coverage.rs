@@ -0,0 +1,189 @@
+//! `coverage` command: report which SMuFL recommended glyphs a font has.
+
+use serde_derive::Serialize;
+use serde_json as json;
+
+use crate::glyphnames::GlyphNames;
+use crate::ranges::Ranges;
+use crate::source::{Layered, Source};
+
+/// Coverage of one SMuFL range.
+#[derive(Serialize)]
+struct RangeCoverage {
+    name: String,
+    description: String,
+    range_start: String,
+    range_end: String,
+    /// Glyphs the font has at their recommended codepoint.
+    present: Vec<String>,
+    /// Glyphs the font only has stylistic alternates for.
+    alternates_only: Vec<String>,
+    /// Glyphs the font doesn't have at all.
+    missing: Vec<String>,
+}
+
+/// Coverage of every SMuFL range.
+#[derive(Serialize)]
+struct Coverage {
+    font_name: String,
+    font_version: f32,
+    ranges: Vec<RangeCoverage>,
+}
+
+fn usage(executable: &str) -> ! {
+    eprintln!(
+        "Usage: {} coverage report font.svg meta.json \
+         --glyphnames glyphnames.json --ranges ranges.json",
+        executable
+    );
+    std::process::exit(1);
+}
+
+/// Run the command, writing `report.md` and `report.json`.
+pub fn run(executable: &str, mut args: std::env::Args) {
+    let report = args.next().unwrap_or_else(|| usage(executable));
+    let svg = args.next().unwrap_or_else(|| usage(executable));
+    let meta = args.next().unwrap_or_else(|| usage(executable));
+    let mut names = None;
+    let mut ranges = None;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--glyphnames" => {
+                let file = args.next().unwrap_or_else(|| usage(executable));
+                names = Some(GlyphNames::load(&file));
+            }
+            "--ranges" => {
+                let file = args.next().unwrap_or_else(|| usage(executable));
+                ranges = Some(Ranges::load(&file));
+            }
+            _ => usage(executable),
+        }
+    }
+    let names = names.unwrap_or_else(|| usage(executable));
+    let ranges = ranges.unwrap_or_else(|| usage(executable));
+
+    let font = Layered::new(Source::load(&svg, &meta));
+    let coverage = coverage(&font, &names, &ranges);
+
+    let markdown = format!("{}.md", report);
+    std::fs::write(&markdown, to_markdown(&coverage)).unwrap_or_else(|e| {
+        eprintln!("Failed to write {}: {}", markdown, e);
+        std::process::exit(1);
+    });
+    let report = format!("{}.json", report);
+    std::fs::write(&report, json::to_string_pretty(&coverage).unwrap()).unwrap_or_else(|e| {
+        eprintln!("Failed to write {}: {}", report, e);
+        std::process::exit(1);
+    });
+
+    println!("Done!");
+}
+
+fn coverage(font: &Layered, names: &GlyphNames, ranges: &Ranges) -> Coverage {
+    let with_alternates: Vec<u16> = font.alternates.values().cloned().collect();
+
+    let mut coverage = Coverage {
+        font_name: font.metadata.fontName.clone(),
+        font_version: font.metadata.fontVersion,
+        ranges: Vec::new(),
+    };
+    for (name, range) in ranges.sorted() {
+        let mut cover = RangeCoverage {
+            name: name.clone(),
+            description: range.description.clone(),
+            range_start: range.range_start.clone(),
+            range_end: range.range_end.clone(),
+            present: Vec::new(),
+            alternates_only: Vec::new(),
+            missing: Vec::new(),
+        };
+        for glyph in range.glyphs.iter() {
+            let list = match names.codepoint(glyph) {
+                Some(cp) if font.glyphs.contains_key(&cp) => &mut cover.present,
+                Some(cp) if with_alternates.contains(&cp) => &mut cover.alternates_only,
+                _ => &mut cover.missing,
+            };
+            list.push(glyph.clone());
+        }
+        coverage.ranges.push(cover);
+    }
+    coverage
+}
+
+fn to_markdown(coverage: &Coverage) -> String {
+    let mut md = format!(
+        "# SMuFL Coverage of {} {}\n\n\
+         | Range | Codepoints | Present | Alternates Only | Missing |\n\
+         |-------|------------|---------|-----------------|---------|\n",
+        coverage.font_name, coverage.font_version,
+    );
+    for range in coverage.ranges.iter() {
+        md.push_str(&format!(
+            "| {} | {}–{} | {} | {} | {} |\n",
+            range.description,
+            range.range_start,
+            range.range_end,
+            range.present.len(),
+            range.alternates_only.len(),
+            range.missing.len(),
+        ));
+    }
+
+    for range in coverage.ranges.iter() {
+        if range.alternates_only.is_empty() && range.missing.is_empty() {
+            continue;
+        }
+        md.push_str(&format!("\n## {}\n", range.description));
+        if !range.alternates_only.is_empty() {
+            md.push_str(&format!(
+                "Alternates only: {}\n\n",
+                range.alternates_only.join(", ")
+            ));
+        }
+        if !range.missing.is_empty() {
+            md.push_str(&format!("Missing: {}\n", range.missing.join(", ")));
+        }
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{metadata_json, source};
+
+    #[test]
+    fn present_alternates_only_and_missing() {
+        let names = GlyphNames::parse(
+            "glyphnames.json",
+            r#"{
+                "gClef": {"codepoint": "U+E050", "description": ""},
+                "fClef": {"codepoint": "U+E062", "description": ""},
+                "fClef8vb": {"codepoint": "U+E064", "description": ""}
+            }"#,
+        );
+        let ranges = Ranges::parse(
+            "ranges.json",
+            r#"{
+                "clefs": {
+                    "description": "Clefs",
+                    "glyphs": ["gClef", "fClef", "fClef8vb", "unknownClef"],
+                    "range_start": "U+E050",
+                    "range_end": "U+E07F"
+                }
+            }"#,
+        );
+        let glyphs = [("uniE050", "", "M0 250L250 250Z"), ("uniE062.ss01", "&#xF400;", "M0 0Z")];
+        let font = Layered::new(source("base.svg", &glyphs, metadata_json("")));
+        let coverage = coverage(&font, &names, &ranges);
+
+        assert_eq!(coverage.font_name, "Test");
+        let clefs = &coverage.ranges[0];
+        assert_eq!(clefs.present, vec!["gClef"]);
+        assert_eq!(clefs.alternates_only, vec!["fClef"]);
+        assert_eq!(clefs.missing, vec!["fClef8vb", "unknownClef"]);
+        let markdown = to_markdown(&coverage);
+        assert!(markdown.contains("| Clefs | U+E050–U+E07F | 1 | 1 | 2 |\n"));
+        assert!(markdown.contains("Missing: fClef8vb, unknownClef\n"));
+    }
+}
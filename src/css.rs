@@ -0,0 +1,106 @@
+//! CSS output, with each glyph as a data URI background image, for putting
+//! glyphs in text without any other files.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use sfff::STAVE_SPACE;
+use svgdom::Path;
+
+use crate::sprites;
+
+/// Stave spaces per em, as in SMuFL fonts.
+const STAVE_SPACES_PER_EM: f64 = 4.0;
+
+/// Write a CSS file with an `sf` class for glyph elements, and a class per
+/// glyph (`sf-` followed by the `Glyph` variant name in lower camel case) that
+/// sizes the element to the glyph, in ems at four stave spaces per em, and
+/// sits the glyph's origin on the text baseline.  `glyphs` are the variant
+/// names, SMuFL codepoints and paths of the glyphs, and `font` the outlines
+/// they were made from.
+pub fn write(file: &str, font_name: &str, glyphs: &[(&str, u16, String)], font: &HashMap<u16, Path>) {
+    let em = |units: f64| {
+        let em = units / STAVE_SPACE as f64 / STAVE_SPACES_PER_EM;
+        // Adding zero turns -0 into 0.
+        format!("{}em", (em * 1000.0).round() / 1000.0 + 0.0)
+    };
+
+    let mut out = String::new();
+    writeln!(out, "/* {} ScoreFall font, generated by scorefall-font. */", font_name).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, ".sf {{").unwrap();
+    writeln!(out, "  display: inline-block;").unwrap();
+    writeln!(out, "  background-repeat: no-repeat;").unwrap();
+    writeln!(out, "  background-size: 100% 100%;").unwrap();
+    writeln!(out, "}}").unwrap();
+    for (name, cp, path) in glyphs {
+        let [x, y, width, height] = sprites::view_box(font, *cp);
+        let svg = format!(
+            "<svg xmlns='http://www.w3.org/2000/svg' viewBox='{} {} {} {}'><path d='{}'/></svg>",
+            x, y, width, height, path
+        );
+        writeln!(out).unwrap();
+        writeln!(out, ".sf-{} {{", crate::lower_camel(name)).unwrap();
+        writeln!(out, "  width: {};", em(width)).unwrap();
+        writeln!(out, "  height: {};", em(height)).unwrap();
+        // The bottom of the view box is `y + height` below the origin.
+        writeln!(out, "  vertical-align: {};", em(-(y + height))).unwrap();
+        writeln!(out, "  background-image: url(\"data:image/svg+xml,{}\");", escape(&svg)).unwrap();
+        writeln!(out, "}}").unwrap();
+    }
+
+    std::fs::write(file, out).unwrap_or_else(|e| {
+        eprintln!("Failed to write {}: {}", file, e);
+        std::process::exit(1);
+    });
+}
+
+/// Escape SVG for a data URI in a double-quoted CSS string.
+fn escape(svg: &str) -> String {
+    let mut escaped = String::with_capacity(svg.len());
+    for c in svg.chars() {
+        match c {
+            '%' => escaped.push_str("%25"),
+            '#' => escaped.push_str("%23"),
+            '<' => escaped.push_str("%3C"),
+            '>' => escaped.push_str("%3E"),
+            '"' => escaped.push_str("%22"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::temp_file;
+
+    #[test]
+    fn classes_sized_in_ems() {
+        let file = temp_file("glyphs.css");
+        let mut font = HashMap::new();
+        font.insert(0xE0A4, "M0 -60L119.6 -60L119.6 40L0 40Z".parse().unwrap());
+        let glyphs = [
+            ("NoteheadFill", 0xE0A4, "M0 60L120 60L120 -40L0 -40Z".to_string()),
+            ("Space", 0xE000, String::new()),
+        ];
+        write(&file, "Test", &glyphs, &font);
+        let out = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(out.starts_with("/* Test ScoreFall font, generated by scorefall-font. */\n"));
+        assert!(out.contains(concat!(
+            ".sf-noteheadFill {\n",
+            "  width: 0.3em;\n",
+            "  height: 0.25em;\n",
+            "  vertical-align: -0.15em;\n",
+            "  background-image: url(\"data:image/svg+xml,",
+            "%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 -40 120 100'%3E",
+            "%3Cpath d='M0 60L120 60L120 -40L0 -40Z'/%3E%3C/svg%3E\");\n",
+            "}\n",
+        )));
+        assert!(out.contains(".sf-space {\n  width: 0em;\n  height: 0em;\n  vertical-align: 0em;\n"));
+        assert_eq!(escape(r##"<a b="#%">"##), "%3Ca b=%22%23%25%22%3E");
+    }
+}
@@ -0,0 +1,177 @@
+//! ES module output, for web clients to bundle the font instead of parsing
+//! `.sfff` at load time.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde_json as json;
+use sfff::{SfFontMetadata, STAVE_SPACE};
+
+use crate::glyphnames::GlyphNames;
+use crate::smufl_serde::GlyphWithAnchors;
+use crate::vfont;
+
+/// Write `font.js` and its type declarations `font.d.ts` into `dir`.  Each
+/// glyph's path is a separate export (named after its `Glyph` variant, in
+/// lower camel case), so bundlers can leave out the ones that aren't used,
+/// along with its anchors if the SMuFL name of the glyph is known.
+pub fn write(
+    dir: &str,
+    metadata: &SfFontMetadata,
+    glyphs: &[(&str, u16, String)],
+    anchors: &HashMap<String, GlyphWithAnchors>,
+    names: &GlyphNames,
+) {
+    let mut js = String::new();
+    let mut ts = String::new();
+    for out in [&mut js, &mut ts].iter_mut() {
+        writeln!(out, "// {} ScoreFall font, generated by scorefall-font.", metadata.font_name)
+            .unwrap();
+        writeln!(out, "// Don't edit; regenerate it instead.").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "/** Units per stave space. */").unwrap();
+    }
+    writeln!(js, "export const STAVE_SPACE = {};", STAVE_SPACE).unwrap();
+    writeln!(ts, "export declare const STAVE_SPACE: {};", STAVE_SPACE).unwrap();
+    writeln!(ts).unwrap();
+    writeln!(ts, "/** A point, in units of `STAVE_SPACE` per stave space. */").unwrap();
+    writeln!(ts, "export type Point = readonly [number, number];").unwrap();
+
+    // Metrics.
+    let metrics = vfont::metrics(metadata);
+    writeln!(js).unwrap();
+    writeln!(js, "/** Font metrics, in units of `STAVE_SPACE` per stave space. */").unwrap();
+    writeln!(js, "export const metrics = {{").unwrap();
+    writeln!(js, "  fontName: {:?},", metadata.font_name).unwrap();
+    for (field, value) in metrics.iter() {
+        writeln!(js, "  {}: {},", camel(field), value).unwrap();
+    }
+    writeln!(js, "}};").unwrap();
+    writeln!(ts).unwrap();
+    writeln!(ts, "/**").unwrap();
+    writeln!(ts, " * Font metrics, in units of `STAVE_SPACE` per stave space.  Notehead stem").unwrap();
+    writeln!(ts, " * points have y up, as in `.sfff` files.").unwrap();
+    writeln!(ts, " */").unwrap();
+    writeln!(ts, "export interface Metrics {{").unwrap();
+    writeln!(ts, "  readonly fontName: string;").unwrap();
+    for (field, value) in metrics.iter() {
+        let ty = if value.starts_with('[') { "readonly [Point, Point]" } else { "number" };
+        writeln!(ts, "  readonly {}: {};", camel(field), ty).unwrap();
+    }
+    writeln!(ts, "}}").unwrap();
+    writeln!(ts).unwrap();
+    writeln!(ts, "export declare const metrics: Metrics;").unwrap();
+
+    // Glyphs.
+    for (name, cp, path) in glyphs {
+        let id = crate::lower_camel(name);
+        for out in [&mut js, &mut ts].iter_mut() {
+            writeln!(out).unwrap();
+            writeln!(out, "/** `{}` (U+{:04X}). */", name, cp).unwrap();
+        }
+        writeln!(js, "export const {} = {:?};", id, path).unwrap();
+        writeln!(ts, "export declare const {}: string;", id).unwrap();
+
+        let points = names
+            .name(*cp)
+            .and_then(|name| anchors.get(name))
+            .map(points)
+            .unwrap_or_default();
+        if points.is_empty() {
+            continue;
+        }
+        for out in [&mut js, &mut ts].iter_mut() {
+            writeln!(out, "/** Anchors of `{}`, with y down like its path. */", name).unwrap();
+        }
+        writeln!(js, "export const {}Anchors = {{", id).unwrap();
+        writeln!(ts, "export declare const {}Anchors: {{", id).unwrap();
+        for (anchor, [x, y]) in points {
+            writeln!(js, "  {}: [{}, {}],", anchor, x, y).unwrap();
+            writeln!(ts, "  readonly {}: Point;", anchor).unwrap();
+        }
+        writeln!(js, "}};").unwrap();
+        writeln!(ts, "}};").unwrap();
+    }
+
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(format!("{}/font.js", dir), js).unwrap();
+    std::fs::write(format!("{}/font.d.ts", dir), ts).unwrap();
+}
+
+/// The anchors that are given, in the same units as the paths, sorted by name.
+fn points(anchors: &GlyphWithAnchors) -> Vec<(String, [i32; 2])> {
+    let convert = |ss: f64| (ss * STAVE_SPACE as f64).round() as i32;
+    let mut points: Vec<(String, [i32; 2])> = match json::to_value(anchors) {
+        Ok(json::Value::Object(map)) => map
+            .into_iter()
+            .filter_map(|(anchor, point)| {
+                let point = point.as_array()?;
+                let (x, y) = (point.first()?.as_f64()?, point.get(1)?.as_f64()?);
+                Some((anchor, [convert(x), convert(-y)]))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    points.sort();
+    points
+}
+
+/// `stave_line_thickness` => `staveLineThickness`.
+fn camel(field: &str) -> String {
+    let mut parts = field.split('_');
+    let mut output = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            output.push(c.to_ascii_uppercase());
+            output.extend(chars);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_json::tests::sf_metadata;
+    use crate::source::tests::temp_file;
+
+    #[test]
+    fn module_and_declarations() {
+        let dir = temp_file("esm");
+        let glyphs = [
+            ("NoteheadFill", 0xE0A4, "M0 0L10 0Z".to_string()),
+            ("ClefG", 0xE050, "M1 1Z".to_string()),
+        ];
+        let mut anchors = HashMap::new();
+        anchors.insert(
+            "noteheadBlack".to_string(),
+            json::from_str(r#"{"stemUpSE": [1.18, 0.168], "stemDownNW": [0.0, -0.168]}"#)
+                .unwrap(),
+        );
+        write(&dir, &sf_metadata(), &glyphs, &anchors, &GlyphNames::bundled());
+        let js = std::fs::read_to_string(format!("{}/font.js", dir)).unwrap();
+        let ts = std::fs::read_to_string(format!("{}/font.d.ts", dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(js.starts_with("// Test ScoreFall font, generated by scorefall-font.\n"));
+        assert!(js.contains(&format!("export const STAVE_SPACE = {};\n", STAVE_SPACE)));
+        assert!(js.contains("  fontName: \"Test\",\n  staveLineThickness: 10,\n  stemThickness: 11,\n"));
+        assert!(js.contains("  noteheadX: [[0, -50], [101, 50]],\n"));
+        assert!(js.contains("export const noteheadFill = \"M0 0L10 0Z\";\n"));
+        assert!(js.contains("export const clefG = \"M1 1Z\";\n"));
+        // Anchors have y down, like the paths.
+        assert!(js.contains(
+            "export const noteheadFillAnchors = {\n  stemDownNW: [0, 17],\n  stemUpSE: [118, -17],\n};\n"
+        ));
+        assert!(!js.contains("clefGAnchors"));
+
+        assert!(ts.contains("  readonly stemThickness: number;\n"));
+        assert!(ts.contains("  readonly noteheadX: readonly [Point, Point];\n"));
+        assert!(ts.contains("export declare const metrics: Metrics;\n"));
+        assert!(ts.contains("export declare const noteheadFill: string;\n"));
+        assert!(ts.contains(
+            "export declare const noteheadFillAnchors: {\n  readonly stemDownNW: Point;\n  readonly stemUpSE: Point;\n};\n"
+        ));
+    }
+}
@@ -0,0 +1,653 @@
+//! Glyphs beyond the fixed `sfff::Glyph` set.  These are stored as a JSON
+//! table in a file next to the ScoreFall font (see `sidecar`), so the `.sfff`
+//! file stays as `sfff` readers expect it.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json as json;
+
+use crate::classes::Classes;
+use crate::profile::PRIVATE;
+use crate::glyphnames::{parse_codepoint, GlyphNames};
+use crate::ranges::Ranges;
+use crate::source::Layered;
+use crate::unicode::MUSICAL_SYMBOLS;
+
+/// Extra glyph table.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Extras {
+    /// Ligatures, to substitute for a sequence of component glyphs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ligatures: Vec<Ligature>,
+    /// Stylistic alternates and stylistic set members.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternates: Vec<Alternate>,
+    /// Optional glyphs private to the source font, with their classes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub optional: Vec<Optional>,
+    /// SMuFL recommended glyphs that aren't in `sfff::Glyph`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recommended: Vec<Recommended>,
+    /// Composite glyphs built by the converter.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub composites: Vec<Optional>,
+    /// SMuFL codepoint of exported glyphs, by Unicode Musical Symbols
+    /// codepoint, so that text using the Unicode block can be drawn.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub unicode: BTreeMap<u32, u16>,
+}
+
+/// A glyph that replaces a sequence of other glyphs.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ligature {
+    /// SMuFL canonical name, if known.
+    pub name: Option<String>,
+    /// Codepoint of the ligature in the font's optional range, if known.
+    pub codepoint: Option<u16>,
+    /// Codepoints of the glyphs this ligature replaces, in order.
+    pub components: Vec<u16>,
+    /// Outline, in the same format as the ScoreFall glyph paths.
+    pub path: String,
+}
+
+/// A glyph that can be used in place of another glyph.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Alternate {
+    /// SMuFL canonical name.
+    pub name: String,
+    /// Codepoint of the alternate in the font's optional range.
+    pub codepoint: u16,
+    /// Codepoint of the glyph this is an alternate for.
+    pub base: u16,
+    /// Stylistic set type (e.g. "flagsStraight"), or `None` for a stylistic
+    /// alternate that isn't part of a set.
+    pub set: Option<String>,
+    /// Outline, in the same format as the ScoreFall glyph paths.
+    pub path: String,
+}
+
+/// A glyph in the optional range (U+F400–U+FFFF) of the source font.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Optional {
+    /// SMuFL name, as given by the source font.
+    pub name: String,
+    /// Codepoint in the font's optional range.
+    pub codepoint: u16,
+    /// Classes the glyph belongs to (e.g. "noteheads").
+    pub classes: Vec<String>,
+    /// Outline, in the same format as the ScoreFall glyph paths.
+    pub path: String,
+}
+
+/// A SMuFL recommended glyph that isn't in `sfff::Glyph`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Recommended {
+    /// Stable numeric ID, which is the SMuFL codepoint.  Codepoints are never
+    /// reassigned by SMuFL, so the ID doesn't change with the selection of
+    /// glyphs or between fonts, and can't clash with a `sfff::Glyph`.
+    pub id: u16,
+    /// SMuFL canonical name.
+    pub name: String,
+    /// Requested SMuFL classes the glyph belongs to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub classes: Vec<String>,
+    /// Requested SMuFL ranges the glyph belongs to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ranges: Vec<String>,
+    /// Outline, in the same format as the ScoreFall glyph paths.
+    pub path: String,
+}
+
+impl Extras {
+    /// Returns true if there's nothing to write.
+    pub fn is_empty(&self) -> bool {
+        self.ligatures.is_empty()
+            && self.alternates.is_empty()
+            && self.optional.is_empty()
+            && self.recommended.is_empty()
+            && self.composites.is_empty()
+            && self.unicode.is_empty()
+    }
+
+    /// Add every ligature outline in the font, naming those that the
+    /// metadata's `ligatures` list describes.
+    pub fn add_ligatures(&mut self, font: &Layered, names: &GlyphNames) {
+        // Component codepoints => (name, codepoint) from the metadata.
+        let mut described = HashMap::new();
+        for (name, ligature) in font.metadata.ligatures.iter() {
+            let components: Option<Vec<u16>> = ligature
+                .componentGlyphs
+                .iter()
+                .map(|c| names.codepoint(c))
+                .collect();
+            if let Some(components) = components {
+                let codepoint = parse_codepoint(&ligature.codepoint);
+                described.insert(components, (name.clone(), codepoint));
+            }
+        }
+
+        let mut sequences: Vec<&Vec<u16>> = font.ligatures.keys().collect();
+        sequences.sort();
+        for components in sequences {
+            let (name, codepoint) = match described.remove(components) {
+                Some((name, codepoint)) => (Some(name), codepoint),
+                None => (None, None),
+            };
+            self.ligatures.push(Ligature {
+                name,
+                codepoint,
+                components: components.clone(),
+                path: crate::svg_path(&font.ligatures[components]),
+            });
+        }
+
+        if !described.is_empty() {
+            println!("{} ligatures in metadata have no outline", described.len());
+        }
+    }
+
+    /// Add alternates from the metadata's `glyphsWithAlternates` and `sets`.
+    /// If `set_types` is given, only members of sets of those types are added.
+    pub fn add_alternates(
+        &mut self,
+        font: &Layered,
+        names: &GlyphNames,
+        set_types: Option<&[String]>,
+    ) {
+        // Codepoint => (name, base name, set type).
+        let mut found = HashMap::new();
+        if set_types.is_none() {
+            for (base, alternates) in font.metadata.glyphsWithAlternates.iter() {
+                for alt in alternates.alternates.iter() {
+                    if let Some(cp) = parse_codepoint(&alt.codepoint) {
+                        found.insert(cp, (&alt.name, base, None));
+                    }
+                }
+            }
+        }
+        for set in font.metadata.sets.values() {
+            if let Some(types) = set_types {
+                if !types.contains(&set.r#type) {
+                    continue;
+                }
+            }
+            for glyph in set.glyphs.iter() {
+                if let Some(cp) = parse_codepoint(&glyph.codepoint) {
+                    found.insert(cp, (&glyph.name, &glyph.alternateFor, Some(&set.r#type)));
+                }
+            }
+        }
+
+        let mut codepoints: Vec<u16> = found.keys().cloned().collect();
+        codepoints.sort();
+        let mut missing = 0;
+        for codepoint in codepoints {
+            let (name, base_name, set) = found[&codepoint];
+            let base = font
+                .alternates
+                .get(&codepoint)
+                .cloned()
+                .or_else(|| names.codepoint(base_name));
+            let (base, path) = match (base, font.glyphs.get(&codepoint)) {
+                (Some(base), Some(path)) => (base, path),
+                _ => {
+                    missing += 1;
+                    continue;
+                }
+            };
+            self.alternates.push(Alternate {
+                name: name.clone(),
+                codepoint,
+                base,
+                set: set.cloned(),
+                path: crate::svg_path(path),
+            });
+        }
+
+        if missing != 0 {
+            println!("{} alternates in metadata have no outline", missing);
+        }
+    }
+
+    /// Add the metadata's `optionalGlyphs` that belong to any of `classes`.
+    pub fn add_optional(&mut self, font: &Layered, classes: &[String]) {
+        let mut found: Vec<(u16, &String, &Vec<String>)> = Vec::new();
+        for (name, glyph) in font.metadata.optionalGlyphs.iter() {
+            let glyph_classes = match glyph.classes {
+                Some(ref glyph_classes) => glyph_classes,
+                None => continue,
+            };
+            if !glyph_classes.iter().any(|c| classes.contains(c)) {
+                continue;
+            }
+            if let Some(cp) = parse_codepoint(&glyph.codepoint) {
+                found.push((cp, name, glyph_classes));
+            }
+        }
+        found.sort();
+
+        let mut missing = 0;
+        for (codepoint, name, classes) in found {
+            let path = match font.glyphs.get(&codepoint) {
+                Some(path) => path,
+                None => {
+                    missing += 1;
+                    continue;
+                }
+            };
+            self.optional.push(Optional {
+                name: name.clone(),
+                codepoint,
+                classes: classes.clone(),
+                path: crate::svg_path(path),
+            });
+        }
+
+        if missing != 0 {
+            println!("{} optional glyphs in metadata have no outline", missing);
+        }
+    }
+
+    /// Add every member of the SMuFL `classes` that isn't `used` already.
+    pub fn add_classes(
+        &mut self,
+        font: &Layered,
+        names: &GlyphNames,
+        smufl: &Classes,
+        classes: &[String],
+        used: &[u16],
+    ) {
+        let groups: Vec<(&String, &[String])> =
+            classes.iter().map(|c| (c, smufl.members(c))).collect();
+        self.add_groups(font, names, &groups, used, false);
+    }
+
+    /// Add every glyph of the SMuFL `ranges` that isn't `used` already.
+    pub fn add_ranges(
+        &mut self,
+        font: &Layered,
+        names: &GlyphNames,
+        smufl: &Ranges,
+        ranges: &[String],
+        used: &[u16],
+    ) {
+        let groups: Vec<(&String, &[String])> = ranges
+            .iter()
+            .map(|r| {
+                let (key, range) = smufl.find(r);
+                (key, range.glyphs.as_slice())
+            })
+            .collect();
+        self.add_groups(font, names, &groups, used, true);
+    }
+
+    /// Add the member glyphs of classes or ranges, recording membership.
+    fn add_groups(
+        &mut self,
+        font: &Layered,
+        names: &GlyphNames,
+        groups: &[(&String, &[String])],
+        used: &[u16],
+        ranges: bool,
+    ) {
+        let mut index: HashMap<u16, usize> = self
+            .recommended
+            .iter()
+            .enumerate()
+            .map(|(i, glyph)| (glyph.id, i))
+            .collect();
+        let mut unknown = 0;
+        let mut missing = BTreeSet::new();
+        for (group, members) in groups {
+            for name in members.iter() {
+                let id = match names.codepoint(name) {
+                    Some(cp) => cp,
+                    None => {
+                        unknown += 1;
+                        continue;
+                    }
+                };
+                if used.contains(&id) {
+                    continue;
+                }
+                let i = match index.get(&id) {
+                    Some(i) => *i,
+                    None => {
+                        let path = match font.glyphs.get(&id) {
+                            Some(path) => path,
+                            None => {
+                                missing.insert(id);
+                                continue;
+                            }
+                        };
+                        self.recommended.push(Recommended {
+                            id,
+                            name: name.clone(),
+                            classes: Vec::new(),
+                            ranges: Vec::new(),
+                            path: crate::svg_path(path),
+                        });
+                        index.insert(id, self.recommended.len() - 1);
+                        self.recommended.len() - 1
+                    }
+                };
+                let glyph = &mut self.recommended[i];
+                let groups = if ranges { &mut glyph.ranges } else { &mut glyph.classes };
+                if !groups.contains(group) {
+                    groups.push((*group).clone());
+                }
+            }
+        }
+        self.recommended.sort_by_key(|glyph| glyph.id);
+
+        let what = if ranges { "range" } else { "class" };
+        if unknown != 0 {
+            println!("{} {} members are missing from glyph names", unknown, what);
+        }
+        if !missing.is_empty() {
+            println!("{} {} members have no outline", missing.len(), what);
+        }
+    }
+
+    /// Add the ScoreFall private symbols that aren't `used` in `sfff::Glyph`.
+    pub fn add_profile(&mut self, font: &Layered, used: &[u16]) {
+        for (id, name, _) in PRIVATE.iter() {
+            if used.contains(id) || self.recommended.iter().any(|g| g.id == *id) {
+                continue;
+            }
+            if let Some(path) = font.glyphs.get(id) {
+                self.recommended.push(Recommended {
+                    id: *id,
+                    name: name.to_string(),
+                    classes: Vec::new(),
+                    ranges: Vec::new(),
+                    path: crate::svg_path(path),
+                });
+            }
+        }
+        self.recommended.sort_by_key(|glyph| glyph.id);
+    }
+
+    /// Add the composite glyphs that were `built`, unless they're `used` in
+    /// `sfff::Glyph` or already in this table.
+    pub fn add_composites(&mut self, font: &Layered, built: &[(String, u16)], used: &[u16]) {
+        for (name, codepoint) in built {
+            if used.contains(codepoint)
+                || self.recommended.iter().any(|g| g.id == *codepoint)
+                || self.optional.iter().any(|g| g.codepoint == *codepoint)
+            {
+                continue;
+            }
+            self.composites.push(Optional {
+                name: name.clone(),
+                codepoint: *codepoint,
+                classes: Vec::new(),
+                path: crate::svg_path(&font.glyphs[codepoint]),
+            });
+        }
+    }
+
+    /// Map the Unicode Musical Symbols block to every exported glyph that has
+    /// an equivalent, whether `used` in `sfff::Glyph` or in this table.
+    pub fn add_unicode(&mut self, used: &[u16]) {
+        let mut exported: Vec<u16> = used.to_vec();
+        exported.extend(self.recommended.iter().map(|glyph| glyph.id));
+        exported.extend(self.optional.iter().map(|glyph| glyph.codepoint));
+        exported.extend(self.alternates.iter().map(|glyph| glyph.codepoint));
+        exported.extend(self.composites.iter().map(|glyph| glyph.codepoint));
+
+        for (unicode, smufl, _) in MUSICAL_SYMBOLS.iter() {
+            if exported.contains(smufl) {
+                self.unicode.insert(*unicode, *smufl);
+            }
+        }
+    }
+
+    /// Write the table next to the ScoreFall font `font_file`, exiting with a
+    /// message on failure.
+    pub fn write(&self, font_file: &str) {
+        let file = sidecar(font_file);
+        std::fs::write(&file, json::to_string(self).unwrap()).unwrap_or_else(|e| {
+            eprintln!("Failed to write {}: {}", file, e);
+            std::process::exit(1);
+        });
+    }
+}
+
+/// File the extra glyph table of the ScoreFall font `font_file` goes in: the
+/// font's file with the extension `.extra.json`.
+pub fn sidecar(font_file: &str) -> String {
+    let file = std::path::Path::new(font_file).with_extension("extra.json");
+    file.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{metadata_json, source, temp_file};
+
+    fn names() -> GlyphNames {
+        GlyphNames::parse(
+            "glyphnames.json",
+            r#"{
+                "fClef": {"codepoint": "U+E062", "description": ""},
+                "tuplet8": {"codepoint": "U+E888", "description": ""},
+                "fClef8vb": {"codepoint": "U+E064", "description": ""}
+            }"#,
+        )
+    }
+
+    #[test]
+    fn ligatures_named_from_metadata() {
+        let meta = metadata_json(
+            r#""ligatures": {
+                "fClef8vbLigature": {
+                    "codepoint": "U+F4A0",
+                    "componentGlyphs": ["fClef", "tuplet8"]
+                }
+            }"#,
+        );
+        let glyphs = [
+            ("uniE062_uniE888", "", "M250 250L500 250L500 500Z"),
+            ("uniE050_uniE888", "", "M0 0L250 0L0 250Z"),
+        ];
+        let font = Layered::new(source("base.svg", &glyphs, meta));
+        let mut extras = Extras::default();
+        extras.add_ligatures(&font, &names());
+
+        assert_eq!(extras.ligatures.len(), 2);
+        let described = &extras.ligatures[1];
+        assert_eq!(described.components, vec![0xE062, 0xE888]);
+        assert_eq!(described.name.as_deref(), Some("fClef8vbLigature"));
+        assert_eq!(described.codepoint, Some(0xF4A0));
+        assert_eq!(described.path, "M100-100L200-100L200-200Z");
+        let other = &extras.ligatures[0];
+        assert_eq!(other.components, vec![0xE050, 0xE888]);
+        assert_eq!(other.name, None);
+    }
+
+    #[test]
+    fn table_round_trip() {
+        let mut extras = Extras::default();
+        extras.ligatures.push(Ligature {
+            name: Some("fClef8vbLigature".to_string()),
+            codepoint: Some(0xF4A0),
+            components: vec![0xE062, 0xE888],
+            path: "M0 0L100 0Z".to_string(),
+        });
+        let font_file = temp_file("font.sfff");
+        extras.write(&font_file);
+        let file = sidecar(&font_file);
+        let data = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(sidecar("fonts/modern.sfff"), "fonts/modern.extra.json");
+        let table: Extras = json::from_str(&data).unwrap();
+        assert_eq!(json::to_value(&table).unwrap(), json::to_value(&extras).unwrap());
+        assert!(Extras::default().is_empty());
+        assert!(!table.is_empty());
+    }
+
+    fn alternates_font() -> Layered {
+        let meta = metadata_json(
+            r#""glyphsWithAlternates": {
+                "flag8thUp": {"alternates": [
+                    {"codepoint": "U+F400", "name": "flag8thUpSmall"}
+                ]}
+            },
+            "sets": {
+                "ss03": {
+                    "type": "flagsStraight",
+                    "description": "Straight flags",
+                    "glyphs": [{
+                        "codepoint": "U+F401",
+                        "name": "flag8thUpStraight",
+                        "alternateFor": "flag8thUp"
+                    }]
+                }
+            }"#,
+        );
+        let glyphs = [
+            ("uniE240", "", "M0 0L250 250Z"),
+            ("uniE240.salt01", "&#xF400;", "M250 250L250 500Z"),
+            ("uniE240.ss03", "&#xF401;", "M0 0L500 250Z"),
+        ];
+        Layered::new(source("base.svg", &glyphs, meta))
+    }
+
+    #[test]
+    fn alternates_and_sets() {
+        let font = alternates_font();
+        let mut extras = Extras::default();
+        extras.add_alternates(&font, &GlyphNames::default(), None);
+
+        assert_eq!(extras.alternates.len(), 2);
+        let alternate = &extras.alternates[0];
+        assert_eq!((alternate.name.as_str(), alternate.codepoint), ("flag8thUpSmall", 0xF400));
+        assert_eq!((alternate.base, alternate.set.as_deref()), (0xE240, None));
+        assert_eq!(alternate.path, "M100-100L100-200Z");
+        let member = &extras.alternates[1];
+        assert_eq!((member.name.as_str(), member.codepoint), ("flag8thUpStraight", 0xF401));
+        assert_eq!((member.base, member.set.as_deref()), (0xE240, Some("flagsStraight")));
+    }
+
+    #[test]
+    fn only_sets_of_types() {
+        let font = alternates_font();
+        let mut extras = Extras::default();
+        let types = ["flagsStraight".to_string()];
+        extras.add_alternates(&font, &GlyphNames::default(), Some(&types));
+        assert_eq!(extras.alternates.len(), 1);
+        assert_eq!(extras.alternates[0].codepoint, 0xF401);
+
+        let mut extras = Extras::default();
+        let types = ["flagsShort".to_string()];
+        extras.add_alternates(&font, &GlyphNames::default(), Some(&types));
+        assert!(extras.alternates.is_empty());
+    }
+
+    #[test]
+    fn optional_glyphs_by_class() {
+        let meta = metadata_json(
+            r#""optionalGlyphs": {
+                "noteheadBlackOval": {
+                    "codepoint": "U+F410",
+                    "classes": ["noteheads", "noteheadSetDefault"]
+                },
+                "accidentalSharpThick": {"codepoint": "U+F411", "classes": ["accidentals"]},
+                "noteheadWholeOval": {"codepoint": "U+F412", "classes": ["noteheads"]},
+                "unclassified": {"codepoint": "U+F413"}
+            }"#,
+        );
+        let glyphs = [
+            ("uniF410", "", "M0 250L250 250Z"),
+            ("uniF411", "", "M0 250L250 250Z"),
+            ("uniF413", "", "M0 250L250 250Z"),
+        ];
+        let font = Layered::new(source("base.svg", &glyphs, meta));
+        let mut extras = Extras::default();
+        extras.add_optional(&font, &["noteheads".to_string()]);
+
+        assert_eq!(extras.optional.len(), 1);
+        let optional = &extras.optional[0];
+        assert_eq!((optional.name.as_str(), optional.codepoint), ("noteheadBlackOval", 0xF410));
+        assert_eq!(optional.classes, vec!["noteheads", "noteheadSetDefault"]);
+        assert_eq!(optional.path, "M0-100L100-100Z");
+    }
+
+    #[test]
+    fn class_members_not_used() {
+        let names = GlyphNames::parse(
+            "glyphnames.json",
+            r#"{
+                "accidentalFlat": {"codepoint": "U+E260", "description": ""},
+                "accidentalSharp": {"codepoint": "U+E262", "description": ""},
+                "accidentalDoubleSharp": {"codepoint": "U+E263", "description": ""},
+                "accidentalTripleSharp": {"codepoint": "U+E265", "description": ""}
+            }"#,
+        );
+        let smufl = Classes::parse(
+            "classes.json",
+            r#"{
+                "accidentalsStandard": [
+                    "accidentalFlat", "accidentalSharp", "accidentalDoubleSharp",
+                    "accidentalTripleSharp", "accidentalUnknown"
+                ],
+                "accidentals": ["accidentalDoubleSharp"]
+            }"#,
+        );
+        let glyphs = [
+            ("uniE260", "", "M0 250L250 250Z"),
+            ("uniE262", "", "M0 250L250 250Z"),
+            ("uniE263", "", "M0 250L250 250Z"),
+        ];
+        let font = Layered::new(source("base.svg", &glyphs, metadata_json("")));
+        let mut extras = Extras::default();
+        let classes = ["accidentalsStandard".to_string(), "accidentals".to_string()];
+        extras.add_classes(&font, &names, &smufl, &classes, &[0xE260]);
+
+        assert_eq!(extras.recommended.len(), 2);
+        let sharp = &extras.recommended[0];
+        assert_eq!((sharp.id, sharp.name.as_str()), (0xE262, "accidentalSharp"));
+        assert_eq!(sharp.classes, vec!["accidentalsStandard"]);
+        let double = &extras.recommended[1];
+        assert_eq!((double.id, double.name.as_str()), (0xE263, "accidentalDoubleSharp"));
+        assert_eq!(double.classes, vec!["accidentalsStandard", "accidentals"]);
+        assert!(double.ranges.is_empty());
+    }
+
+    #[test]
+    fn ranges_by_name_or_description() {
+        let names = GlyphNames::parse(
+            "glyphnames.json",
+            r#"{
+                "figbass0": {"codepoint": "U+EA50", "description": ""},
+                "figbass1": {"codepoint": "U+EA51", "description": ""},
+                "figbass2": {"codepoint": "U+EA52", "description": ""}
+            }"#,
+        );
+        let smufl = Ranges::parse(
+            "ranges.json",
+            r#"{
+                "figuredBass": {
+                    "description": "Figured bass",
+                    "glyphs": ["figbass0", "figbass1", "figbass2"],
+                    "range_start": "U+EA50",
+                    "range_end": "U+EA6F"
+                }
+            }"#,
+        );
+        let glyphs = [("uniEA50", "", "M0 250L250 250Z"), ("uniEA52", "", "M0 250L250 250Z")];
+        let font = Layered::new(source("base.svg", &glyphs, metadata_json("")));
+        for range in ["figuredBass", "figured bass"].iter() {
+            let mut extras = Extras::default();
+            extras.add_ranges(&font, &names, &smufl, &[range.to_string()], &[]);
+
+            let ids: Vec<u16> = extras.recommended.iter().map(|g| g.id).collect();
+            assert_eq!(ids, vec![0xEA50, 0xEA52]);
+            assert_eq!(extras.recommended[0].ranges, vec!["figuredBass"]);
+            assert!(extras.recommended[0].classes.is_empty());
+        }
+    }
+}
@@ -0,0 +1,419 @@
+//! JSON representation of a ScoreFall font, for debugging and non-Rust
+//! consumers, and the `import` command that compiles it back to `.sfff`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json as json;
+use sfff::{GlyphsBuilder, SfFontMetadata};
+
+use crate::extra::Extras;
+use crate::glyphnames::GlyphNames;
+use crate::smufl_serde::{GlyphBBox, GlyphWithAnchors};
+
+/// Everything `SfFontMetadata::write` writes, apart from the glyph paths.
+/// Lengths are in ScoreFall units.
+#[derive(Serialize, Deserialize)]
+pub struct Metadata {
+    pub sffonts_version: u32,
+    pub font_name: String,
+    pub stave_line_thickness: i32,
+    pub stem_thickness: i32,
+    pub ledger_line_thickness: i32,
+    pub ledger_line_extension: i32,
+    pub slur_endpoint_thickness: i32,
+    pub slur_midpoint_thickness: i32,
+    pub barline_thickness: i32,
+    pub thick_barline_thickness: i32,
+    pub barlines_space: i32,
+    pub barline_repeatdot_space: i32,
+    pub bracket_thickness: i32,
+    pub subbracket_thickness: i32,
+    pub hairpin_thickness: i32,
+    pub rehearsal_box_thickness: i32,
+    pub notehead: [[i32; 2]; 2],
+    pub notehead_x: [[i32; 2]; 2],
+    pub notehead_diamond: [[i32; 2]; 2],
+    pub notehead_triangle: [[i32; 2]; 2],
+    pub notehead_slash: [[i32; 2]; 2],
+    pub notehead_half: [[i32; 2]; 2],
+    pub notehead_half_x: [[i32; 2]; 2],
+    pub notehead_half_diamond: [[i32; 2]; 2],
+    pub notehead_half_triangle: [[i32; 2]; 2],
+    pub notehead_half_slash: [[i32; 2]; 2],
+    pub notehead_whole: [[i32; 2]; 2],
+    pub notehead_whole_x: [[i32; 2]; 2],
+    pub notehead_whole_diamond: [[i32; 2]; 2],
+    pub notehead_whole_triangle: [[i32; 2]; 2],
+    pub notehead_whole_slash: [[i32; 2]; 2],
+    pub notehead_double: [[i32; 2]; 2],
+    pub notehead_double_x: [[i32; 2]; 2],
+    pub notehead_double_diamond: [[i32; 2]; 2],
+    pub notehead_double_triangle: [[i32; 2]; 2],
+    pub notehead_double_slash: [[i32; 2]; 2],
+}
+
+/// A glyph of `sfff::Glyph`.
+#[derive(Serialize, Deserialize)]
+pub struct Glyph {
+    /// `Glyph` variant name.
+    pub glyph: String,
+    /// SMuFL codepoint the glyph was taken from.
+    pub codepoint: String,
+    /// SMuFL canonical name, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Outline, in the same format as the ScoreFall glyph paths.
+    pub path: String,
+    /// SMuFL anchors, in stave spaces with the y axis up.  Not used by
+    /// `import`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub anchors: BTreeMap<String, [f32; 2]>,
+    /// SMuFL bounding box, in stave spaces with the y axis up.  Not used by
+    /// `import`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<GlyphBBox>,
+}
+
+/// A whole ScoreFall font.
+#[derive(Serialize, Deserialize)]
+pub struct Font {
+    pub metadata: Metadata,
+    pub glyphs: Vec<Glyph>,
+    /// The extra glyph table, if any.
+    #[serde(default, skip_serializing_if = "Extras::is_empty")]
+    pub extras: Extras,
+}
+
+impl Metadata {
+    /// Copy of the ScoreFall metadata.
+    pub fn new(m: &SfFontMetadata) -> Metadata {
+        Metadata {
+            sffonts_version: m.sffonts_version,
+            font_name: m.font_name.clone(),
+            stave_line_thickness: m.stave_line_thickness,
+            stem_thickness: m.stem_thickness,
+            ledger_line_thickness: m.ledger_line_thickness,
+            ledger_line_extension: m.ledger_line_extension,
+            slur_endpoint_thickness: m.slur_endpoint_thickness,
+            slur_midpoint_thickness: m.slur_midpoint_thickness,
+            barline_thickness: m.barline_thickness,
+            thick_barline_thickness: m.thick_barline_thickness,
+            barlines_space: m.barlines_space,
+            barline_repeatdot_space: m.barline_repeatdot_space,
+            bracket_thickness: m.bracket_thickness,
+            subbracket_thickness: m.subbracket_thickness,
+            hairpin_thickness: m.hairpin_thickness,
+            rehearsal_box_thickness: m.rehearsal_box_thickness,
+            notehead: m.notehead,
+            notehead_x: m.notehead_x,
+            notehead_diamond: m.notehead_diamond,
+            notehead_triangle: m.notehead_triangle,
+            notehead_slash: m.notehead_slash,
+            notehead_half: m.notehead_half,
+            notehead_half_x: m.notehead_half_x,
+            notehead_half_diamond: m.notehead_half_diamond,
+            notehead_half_triangle: m.notehead_half_triangle,
+            notehead_half_slash: m.notehead_half_slash,
+            notehead_whole: m.notehead_whole,
+            notehead_whole_x: m.notehead_whole_x,
+            notehead_whole_diamond: m.notehead_whole_diamond,
+            notehead_whole_triangle: m.notehead_whole_triangle,
+            notehead_whole_slash: m.notehead_whole_slash,
+            notehead_double: m.notehead_double,
+            notehead_double_x: m.notehead_double_x,
+            notehead_double_diamond: m.notehead_double_diamond,
+            notehead_double_triangle: m.notehead_double_triangle,
+            notehead_double_slash: m.notehead_double_slash,
+        }
+    }
+
+    /// The ScoreFall metadata this describes.
+    pub fn into_sfff(self) -> SfFontMetadata {
+        SfFontMetadata {
+            sffonts_version: self.sffonts_version,
+            font_name: self.font_name,
+            stave_line_thickness: self.stave_line_thickness,
+            stem_thickness: self.stem_thickness,
+            ledger_line_thickness: self.ledger_line_thickness,
+            ledger_line_extension: self.ledger_line_extension,
+            slur_endpoint_thickness: self.slur_endpoint_thickness,
+            slur_midpoint_thickness: self.slur_midpoint_thickness,
+            barline_thickness: self.barline_thickness,
+            thick_barline_thickness: self.thick_barline_thickness,
+            barlines_space: self.barlines_space,
+            barline_repeatdot_space: self.barline_repeatdot_space,
+            bracket_thickness: self.bracket_thickness,
+            subbracket_thickness: self.subbracket_thickness,
+            hairpin_thickness: self.hairpin_thickness,
+            rehearsal_box_thickness: self.rehearsal_box_thickness,
+            notehead: self.notehead,
+            notehead_x: self.notehead_x,
+            notehead_diamond: self.notehead_diamond,
+            notehead_triangle: self.notehead_triangle,
+            notehead_slash: self.notehead_slash,
+            notehead_half: self.notehead_half,
+            notehead_half_x: self.notehead_half_x,
+            notehead_half_diamond: self.notehead_half_diamond,
+            notehead_half_triangle: self.notehead_half_triangle,
+            notehead_half_slash: self.notehead_half_slash,
+            notehead_whole: self.notehead_whole,
+            notehead_whole_x: self.notehead_whole_x,
+            notehead_whole_diamond: self.notehead_whole_diamond,
+            notehead_whole_triangle: self.notehead_whole_triangle,
+            notehead_whole_slash: self.notehead_whole_slash,
+            notehead_double: self.notehead_double,
+            notehead_double_x: self.notehead_double_x,
+            notehead_double_diamond: self.notehead_double_diamond,
+            notehead_double_triangle: self.notehead_double_triangle,
+            notehead_double_slash: self.notehead_double_slash,
+        }
+    }
+}
+
+/// Write the font as JSON.  `glyphs` are the variant names, SMuFL codepoints
+/// and paths of the glyphs; their anchors and bounding boxes are included if
+/// their SMuFL name is known.
+pub fn write(
+    file: &str,
+    metadata: &SfFontMetadata,
+    glyphs: &[(&str, u16, String)],
+    anchors: &HashMap<String, GlyphWithAnchors>,
+    bboxes: &HashMap<String, GlyphBBox>,
+    names: &GlyphNames,
+    extras: &Extras,
+) {
+    let glyphs = glyphs
+        .iter()
+        .map(|(glyph, cp, path)| {
+            let name = names.name(*cp);
+            Glyph {
+                glyph: glyph.to_string(),
+                codepoint: format!("U+{:04X}", cp),
+                name: name.map(str::to_string),
+                path: path.clone(),
+                anchors: name
+                    .and_then(|name| anchors.get(name))
+                    .map(anchor_points)
+                    .unwrap_or_default(),
+                bbox: name.and_then(|name| bboxes.get(name)).map(|bbox| GlyphBBox {
+                    bBoxNE: bbox.bBoxNE,
+                    bBoxSW: bbox.bBoxSW,
+                }),
+            }
+        })
+        .collect();
+    let font = Font { metadata: Metadata::new(metadata), glyphs, extras: extras.clone() };
+
+    let data = json::to_string_pretty(&font).unwrap();
+    std::fs::write(file, data).unwrap_or_else(|e| {
+        eprintln!("Failed to write {}: {}", file, e);
+        std::process::exit(1);
+    });
+}
+
+/// The anchors that are given, by name.
+fn anchor_points(anchors: &GlyphWithAnchors) -> BTreeMap<String, [f32; 2]> {
+    match json::to_value(anchors) {
+        Ok(json::Value::Object(map)) => map
+            .into_iter()
+            .filter_map(|(anchor, point)| Some((anchor, json::from_value(point).ok()?)))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn usage(executable: &str) -> ! {
+    eprintln!("Usage: {} import font.json output.sfff", executable);
+    std::process::exit(1);
+}
+
+/// Run the `import` command, compiling a JSON font to `.sfff`.
+pub fn run(executable: &str, mut args: std::env::Args) {
+    let file = args.next().unwrap_or_else(|| usage(executable));
+    let out = args.next().unwrap_or_else(|| usage(executable));
+    if args.next().is_some() {
+        usage(executable);
+    }
+    import(&file, &out);
+    println!("Done!");
+}
+
+/// Compile the JSON font `file` to the `.sfff` font `out`, with its extra
+/// glyph table next to it.
+fn import(file: &str, out: &str) {
+    let data = std::fs::read_to_string(file).unwrap_or_else(|_| {
+        eprintln!("No such file: {}", file);
+        std::process::exit(1);
+    });
+    let font: Font = json::from_str(&data).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", file, e);
+        std::process::exit(1);
+    });
+
+    let mut glyphs = GlyphsBuilder::new();
+    for (glyph, name, _, _) in crate::glyph_table() {
+        let path = match font.glyphs.iter().find(|g| g.glyph == name) {
+            Some(g) => g.path.clone(),
+            None => {
+                eprintln!("{}: missing glyph {}", file, name);
+                std::process::exit(1);
+            }
+        };
+        glyphs.push(glyph, path);
+    }
+    let glyph_paths = glyphs.into_string();
+    let metadata = font.metadata.into_sfff();
+
+    use std::io::Write;
+
+    let file = std::fs::File::create(out).unwrap();
+    let mut buf_writer = std::io::BufWriter::new(file);
+
+    metadata.write(&mut buf_writer, &glyph_paths).unwrap();
+    buf_writer.flush().unwrap();
+    if !font.extras.is_empty() {
+        font.extras.write(out);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::extra::Optional;
+    use crate::source::tests::temp_file;
+
+    /// ScoreFall metadata with every length different.
+    pub(crate) fn sf_metadata() -> SfFontMetadata {
+        let metadata: Metadata = json::from_str(
+            r#"{
+                "sffonts_version": 1,
+                "font_name": "Test",
+                "stave_line_thickness": 10,
+                "stem_thickness": 11,
+                "ledger_line_thickness": 12,
+                "ledger_line_extension": 13,
+                "slur_endpoint_thickness": 14,
+                "slur_midpoint_thickness": 15,
+                "barline_thickness": 16,
+                "thick_barline_thickness": 17,
+                "barlines_space": 18,
+                "barline_repeatdot_space": 19,
+                "bracket_thickness": 20,
+                "subbracket_thickness": 21,
+                "hairpin_thickness": 22,
+                "rehearsal_box_thickness": 23,
+                "notehead": [[0, -50], [100, 50]],
+                "notehead_x": [[0, -50], [101, 50]],
+                "notehead_diamond": [[0, -50], [102, 50]],
+                "notehead_triangle": [[0, -50], [103, 50]],
+                "notehead_slash": [[0, -50], [104, 50]],
+                "notehead_half": [[0, -50], [105, 50]],
+                "notehead_half_x": [[0, -50], [106, 50]],
+                "notehead_half_diamond": [[0, -50], [107, 50]],
+                "notehead_half_triangle": [[0, -50], [108, 50]],
+                "notehead_half_slash": [[0, -50], [109, 50]],
+                "notehead_whole": [[0, -50], [110, 50]],
+                "notehead_whole_x": [[0, -50], [111, 50]],
+                "notehead_whole_diamond": [[0, -50], [112, 50]],
+                "notehead_whole_triangle": [[0, -50], [113, 50]],
+                "notehead_whole_slash": [[0, -50], [114, 50]],
+                "notehead_double": [[0, -50], [115, 50]],
+                "notehead_double_x": [[0, -50], [116, 50]],
+                "notehead_double_diamond": [[0, -50], [117, 50]],
+                "notehead_double_triangle": [[0, -50], [118, 50]],
+                "notehead_double_slash": [[0, -50], [119, 50]]
+            }"#,
+        )
+        .unwrap();
+        metadata.into_sfff()
+    }
+
+    #[test]
+    fn round_trip() {
+        let file = temp_file("round-trip.json");
+        let metadata = sf_metadata();
+        let glyphs = [
+            ("NoteheadFill", 0xE0A4, "M0 0L10 0Z".to_string()),
+            ("ClefG", 0xE050, "M1 1Z".to_string()),
+        ];
+        let mut anchors = HashMap::new();
+        anchors.insert(
+            "noteheadBlack".to_string(),
+            json::from_str(r#"{"stemUpSE": [1.18, 0.168]}"#).unwrap(),
+        );
+        let mut bboxes = HashMap::new();
+        bboxes.insert(
+            "noteheadBlack".to_string(),
+            GlyphBBox { bBoxNE: [1.18, 0.5], bBoxSW: [0.0, -0.5] },
+        );
+        let mut extras = Extras::default();
+        extras.optional.push(Optional {
+            name: "noteheadBlackSmall".to_string(),
+            codepoint: 0xF400,
+            classes: vec!["noteheads".to_string()],
+            path: "M2 2Z".to_string(),
+        });
+        extras.unicode.insert(0x1D158, 0xE0A4);
+        write(&file, &metadata, &glyphs, &anchors, &bboxes, &GlyphNames::bundled(), &extras);
+        let data = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        let font: Font = json::from_str(&data).unwrap();
+
+        let expected = json::to_value(Metadata::new(&metadata)).unwrap();
+        assert_eq!(json::to_value(&font.metadata).unwrap(), expected);
+        let sfff = font.metadata.into_sfff();
+        assert_eq!(json::to_value(Metadata::new(&sfff)).unwrap(), expected);
+
+        assert_eq!(font.glyphs.len(), 2);
+        let notehead = &font.glyphs[0];
+        assert_eq!(notehead.glyph, "NoteheadFill");
+        assert_eq!(notehead.codepoint, "U+E0A4");
+        assert_eq!(notehead.name.as_deref(), Some("noteheadBlack"));
+        assert_eq!(notehead.path, "M0 0L10 0Z");
+        assert_eq!(notehead.anchors.len(), 1);
+        assert_eq!(notehead.anchors["stemUpSE"], [1.18, 0.168]);
+        let bbox = notehead.bbox.as_ref().unwrap();
+        assert_eq!((bbox.bBoxSW, bbox.bBoxNE), ([0.0, -0.5], [1.18, 0.5]));
+        let clef = &font.glyphs[1];
+        assert_eq!(clef.name.as_deref(), Some("gClef"));
+        assert!(clef.anchors.is_empty() && clef.bbox.is_none());
+
+        assert_eq!(json::to_value(&font.extras).unwrap(), json::to_value(&extras).unwrap());
+    }
+
+    #[test]
+    fn import_compiles_written_font() {
+        let file = temp_file("import.json");
+        let out = temp_file("import.sfff");
+        let metadata = sf_metadata();
+        let table = crate::glyph_table();
+        let glyphs: Vec<(&str, u16, String)> = table
+            .iter()
+            .enumerate()
+            .map(|(i, (_, name, _, cp))| (*name, *cp, format!("M{} 0L0 {}Z", i, i)))
+            .collect();
+        let mut extras = Extras::default();
+        extras.unicode.insert(0x1D158, 0xE0A4);
+        let names = GlyphNames::bundled();
+        write(&file, &metadata, &glyphs, &HashMap::new(), &HashMap::new(), &names, &extras);
+        import(&file, &out);
+        let sfff = std::fs::read(&out).unwrap();
+        let table_file = crate::extra::sidecar(&out);
+        let imported = std::fs::read_to_string(&table_file).unwrap();
+        let imported: Extras = json::from_str(&imported).unwrap();
+        for file in [&file, &out, &table_file].iter() {
+            std::fs::remove_file(file).unwrap();
+        }
+
+        // The same bytes as writing the font directly.
+        let mut builder = GlyphsBuilder::new();
+        for ((glyph, _, _, _), (_, _, path)) in crate::glyph_table().into_iter().zip(&glyphs) {
+            builder.push(glyph, path.clone());
+        }
+        let mut expected = Vec::new();
+        metadata.write(&mut expected, &builder.into_string()).unwrap();
+        assert!(sfff == expected);
+        assert_eq!(json::to_value(&imported).unwrap(), json::to_value(&extras).unwrap());
+    }
+}
@@ -0,0 +1,161 @@
+//! SMuFL canonical glyph names, from `glyphnames.json` and font metadata.
+
+use std::collections::HashMap;
+
+use serde_json as json;
+
+use crate::smufl_serde::{GlyphName, SMuFLMetadata};
+
+/// Mapping between SMuFL canonical glyph names and codepoints.
+#[derive(Default)]
+pub struct GlyphNames {
+    codepoints: HashMap<String, u16>,
+    /// The first name (in sort order) of each codepoint.
+    names: HashMap<u16, String>,
+}
+
+impl GlyphNames {
+    /// The names of the glyphs in `sfff::Glyph`, as `glyph_table` gives them.
+    pub fn bundled() -> GlyphNames {
+        let mut names = GlyphNames::default();
+        for (_, _, name, cp) in crate::glyph_table() {
+            names.insert(name.to_string(), cp);
+        }
+        names
+    }
+
+    /// Load SMuFL's `glyphnames.json`, exiting with a message on failure.
+    pub fn load(path: &str) -> GlyphNames {
+        let data = std::fs::read_to_string(path).unwrap_or_else(|_| {
+            eprintln!("No such file: {}", path);
+            std::process::exit(1);
+        });
+        GlyphNames::parse(path, &data)
+    }
+
+    /// Read glyph names from the text of the file `path`.
+    pub fn parse(path: &str, data: &str) -> GlyphNames {
+        let entries: HashMap<String, GlyphName> =
+            json::from_str(data).unwrap_or_else(|e| {
+                eprintln!("Failed to parse glyph names {}: {}", path, e);
+                std::process::exit(1);
+            });
+
+        let mut names = GlyphNames::default();
+        for (name, entry) in entries {
+            if let Some(cp) = parse_codepoint(&entry.codepoint) {
+                names.insert(name, cp);
+            }
+        }
+        names
+    }
+
+    /// Add the names of optional glyphs (alternates, set members, ligatures)
+    /// listed in the font's metadata.
+    pub fn add_metadata(&mut self, meta: &SMuFLMetadata) {
+        let mut found = Vec::new();
+        for alternates in meta.glyphsWithAlternates.values() {
+            for alt in alternates.alternates.iter() {
+                found.push((alt.name.clone(), alt.codepoint.clone()));
+            }
+        }
+        for set in meta.sets.values() {
+            for glyph in set.glyphs.iter() {
+                found.push((glyph.name.clone(), glyph.codepoint.clone()));
+            }
+        }
+        for (name, ligature) in meta.ligatures.iter() {
+            found.push((name.clone(), ligature.codepoint.clone()));
+        }
+        for (name, optional) in meta.optionalGlyphs.iter() {
+            found.push((name.clone(), optional.codepoint.clone()));
+        }
+
+        for (name, cp) in found {
+            if self.codepoints.contains_key(&name) {
+                continue;
+            }
+            if let Some(cp) = parse_codepoint(&cp) {
+                self.insert(name, cp);
+            }
+        }
+    }
+
+    /// Look up the codepoint of a glyph by its canonical name.
+    pub fn codepoint(&self, name: &str) -> Option<u16> {
+        self.codepoints.get(name).cloned()
+    }
+
+    /// Look up the canonical name of the glyph at a codepoint.
+    pub fn name(&self, codepoint: u16) -> Option<&str> {
+        self.names.get(&codepoint).map(String::as_str)
+    }
+
+    /// Add a name that isn't there yet.
+    fn insert(&mut self, name: String, codepoint: u16) {
+        match self.names.get(&codepoint) {
+            Some(first) if *first <= name => {}
+            _ => {
+                self.names.insert(codepoint, name.clone());
+            }
+        }
+        self.codepoints.insert(name, codepoint);
+    }
+}
+
+/// Parse a SMuFL "U+E050" style codepoint.
+pub fn parse_codepoint(cp: &str) -> Option<u16> {
+    u16::from_str_radix(cp.trim_start_matches("U+"), 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{bravura, has_bbox, metadata_json};
+
+    #[test]
+    fn names_and_codepoints() {
+        let mut names = GlyphNames::parse(
+            "glyphnames.json",
+            r#"{
+                "gClef": {"codepoint": "U+E050", "description": "G clef"},
+                "fClef": {"codepoint": "U+E062", "description": "F clef"}
+            }"#,
+        );
+        names.add_metadata(&metadata_json(
+            r#""optionalGlyphs": {
+                "gClefSmall": {"codepoint": "U+F472"},
+                "gClef": {"codepoint": "U+F000"}
+            }"#,
+        ));
+
+        assert_eq!(names.codepoint("gClef"), Some(0xE050));
+        assert_eq!(names.codepoint("gClefSmall"), Some(0xF472));
+        assert_eq!(names.codepoint("cClef"), None);
+        assert_eq!(names.name(0xE062), Some("fClef"));
+        assert_eq!(names.name(0xF000), None);
+        assert_eq!(parse_codepoint("U+E0A4"), Some(0xE0A4));
+        assert_eq!(parse_codepoint("E0A4"), Some(0xE0A4));
+        assert_eq!(parse_codepoint("U+1D11E"), None);
+    }
+
+    #[test]
+    fn bundled_names_match_bravura() {
+        let names = GlyphNames::bundled();
+        let font = bravura();
+        let table = crate::glyph_table();
+        let unnamed: Vec<&str> = table
+            .iter()
+            .filter(|(_, _, name, cp)| names.name(*cp) != Some(*name))
+            .map(|(_, name, _, _)| *name)
+            .collect();
+        assert!(unnamed.is_empty(), "{:?}", unnamed);
+        let mismatched: Vec<&str> = table
+            .iter()
+            .filter(|(_, _, name, cp)| !has_bbox(&font, name, *cp))
+            .map(|(_, _, name, _)| *name)
+            .collect();
+        assert!(mismatched.is_empty(), "{:?}", mismatched);
+        assert_eq!(names.codepoint("noteheadBlack"), Some(0xE0A4));
+    }
+}
@@ -0,0 +1,181 @@
+//! Large time signature digits, for time signatures written above the staff
+//! or between staves instead of on each staff.
+
+use svgdom::Path;
+
+use crate::glyphnames::parse_codepoint;
+use crate::outline;
+use crate::smufl_serde::{Set, SetGlyph};
+use crate::source::Layered;
+use crate::weight;
+
+/// SMuFL stylistic set type of large time signature digits.
+pub const SET_TYPE: &str = "timeSigsLarge";
+
+/// Codepoint of time signature 0; the other digits follow it.
+const TIME_SIG_0: u16 = 0xE080;
+
+/// Codepoint synthesized large digits are given (the same as Bravura's
+/// `timeSig0Large`), again followed by the other digits.
+const TIME_SIG_0_LARGE: u16 = 0xF440;
+
+/// Make sure the font has a large variant of each time signature digit, in a
+/// `timeSigsLarge` set.  Digits that the source fonts don't provide are made
+/// by scaling the regular digits by `scale`.  Returns the number of digits
+/// synthesized.
+///
+/// A plain scaled-up digit would have strokes `scale` times as heavy as the
+/// regular digits.  So the scaled outline is thinned (see `weight::embolden`)
+/// until its strokes are only the square root of `scale` times as heavy, the
+/// same thickening as the line thicknesses of a small optical size get (see
+/// `optical::adjust_engraving`).
+pub fn synthesize(font: &mut Layered, scale: f32) -> usize {
+    let scale = f64::from(scale);
+    let mut synthesized = Vec::new();
+    let mut count = 0;
+    for digit in 0..10 {
+        let base = TIME_SIG_0 + digit;
+        let base_name = format!("timeSig{}", digit);
+        if has_large(font, &base_name) {
+            continue;
+        }
+        let name = format!("{}Large", base_name);
+        let codepoint = TIME_SIG_0_LARGE + digit;
+        if font.glyphs.contains_key(&codepoint) {
+            println!("{}: U+{:04X} is already used", name, codepoint);
+            continue;
+        }
+        if let Some(other) = claimed(font, codepoint).filter(|other| *other != name) {
+            println!("{}: U+{:04X} is already {} in the metadata", name, codepoint, other);
+            continue;
+        }
+        // A set member without an outline only needs one.
+        let listed = members(font).any(|g| parse_codepoint(&g.codepoint) == Some(codepoint));
+        let path = match font.glyphs.get(&base) {
+            Some(path) => enlarge(path, scale),
+            None => continue,
+        };
+
+        font.glyphs.insert(codepoint, path);
+        font.alternates.insert(codepoint, base);
+        count += 1;
+        if listed {
+            continue;
+        }
+        synthesized.push(SetGlyph {
+            codepoint: format!("U+{:04X}", codepoint),
+            name,
+            alternateFor: base_name,
+        });
+    }
+
+    if synthesized.is_empty() {
+        return count;
+    }
+    let sets = &mut font.metadata.sets;
+    let key = sets
+        .iter()
+        .find(|(_, set)| set.r#type == SET_TYPE)
+        .map(|(key, _)| key.clone())
+        .unwrap_or_else(|| SET_TYPE.to_string());
+    sets.entry(key)
+        .or_insert_with(|| Set {
+            r#type: SET_TYPE.to_string(),
+            description: "Large time signatures".to_string(),
+            glyphs: Vec::new(),
+        })
+        .glyphs
+        .extend(synthesized);
+    count
+}
+
+/// Whether a `timeSigsLarge` set has an outline for the alternate of
+/// `base_name`.
+fn has_large(font: &Layered, base_name: &str) -> bool {
+    members(font)
+        .filter(|glyph| glyph.alternateFor == base_name)
+        .filter_map(|glyph| parse_codepoint(&glyph.codepoint))
+        .any(|cp| font.glyphs.contains_key(&cp))
+}
+
+/// Members of the font's `timeSigsLarge` sets.
+fn members(font: &Layered) -> impl Iterator<Item = &SetGlyph> {
+    font.metadata
+        .sets
+        .values()
+        .filter(|set| set.r#type == SET_TYPE)
+        .flat_map(|set| set.glyphs.iter())
+}
+
+/// Scale a digit up by `scale`, with its strokes thinned back to the square
+/// root of `scale` times their weight.
+fn enlarge(path: &Path, scale: f64) -> Path {
+    let heavier = weight::stroke_width(path) * (scale - scale.sqrt());
+    weight::embolden(&outline::transform(path, scale, 0.0, 0.0), -heavier / 2.0)
+}
+
+/// Name of the optional glyph, alternate, set member or ligature the metadata
+/// gives `codepoint` to, if any.
+fn claimed(font: &Layered, codepoint: u16) -> Option<&str> {
+    let meta = &font.metadata;
+    let is = |cp: &str| parse_codepoint(cp) == Some(codepoint);
+    let optional = meta.optionalGlyphs.iter().filter(|(_, g)| is(&g.codepoint)).map(|(n, _)| n);
+    let alternates = meta.glyphsWithAlternates.values().flat_map(|a| a.alternates.iter());
+    let alternates = alternates.filter(|a| is(&a.codepoint)).map(|a| &a.name);
+    let members = meta.sets.values().flat_map(|set| set.glyphs.iter());
+    let members = members.filter(|g| is(&g.codepoint)).map(|g| &g.name);
+    let ligatures = meta.ligatures.iter().filter(|(_, l)| is(&l.codepoint)).map(|(n, _)| n);
+    optional.chain(alternates).chain(members).chain(ligatures).next().map(|n| n.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{metadata_json, source};
+    use sfff::STAVE_SPACE;
+
+    /// A square ring 1.6 stave spaces across, with 0.4 stave space strokes.
+    const RING: &str = "M0 0L400 0L400 400L0 400ZM100 100L100 300L300 300L300 100Z";
+
+    #[test]
+    fn strokes_thickened_by_square_root() {
+        let ss = STAVE_SPACE as f64;
+        let font = source("base.svg", &[("uniE080", "", RING)], metadata_json(""));
+        let ring = &font.glyphs[&0xE080];
+        assert!((weight::stroke_width(ring) - 0.4 * ss).abs() < 1e-9);
+
+        let large = enlarge(ring, 4.0);
+        assert!((weight::stroke_width(&large) - 0.8 * ss).abs() < 1e-9);
+        let bounds = outline::bounds(&large).unwrap();
+        for (a, b) in bounds.iter().zip([0.4, 0.4, 6.0, 6.0].iter()) {
+            assert!((a - b * ss).abs() < 1e-9, "{:?}", bounds);
+        }
+    }
+
+    #[test]
+    fn skips_claimed_codepoints() {
+        let meta = metadata_json(
+            r#""optionalGlyphs": {"timeSig1Wide": {"codepoint": "U+F441"}},
+            "sets": {"ss04": {"type": "timeSigsLarge", "description": "", "glyphs": [
+                {"codepoint": "U+F442", "name": "timeSig2Large", "alternateFor": "timeSig2"}
+            ]}}"#,
+        );
+        let glyphs = [
+            ("uniE080", "", RING),
+            ("uniE081", "", RING),
+            ("uniE082", "", RING),
+            ("uniE083", "", RING),
+            ("uniF443", "", RING),
+        ];
+        let mut font = Layered::new(source("base.svg", &glyphs, meta));
+        assert_eq!(synthesize(&mut font, 2.0), 2);
+
+        assert!(font.glyphs.contains_key(&0xF440));
+        assert!(!font.glyphs.contains_key(&0xF441));
+        assert_eq!(font.alternates[&0xF442], 0xE082);
+        assert!(!font.alternates.contains_key(&0xF443));
+        let set = &font.metadata.sets["ss04"];
+        let names: Vec<&str> = set.glyphs.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["timeSig2Large", "timeSig0Large"]);
+    }
+}
@@ -0,0 +1,255 @@
+//! Glyph names of fonts made for LilyPond (like Emmentaler and Scorlatti),
+//! which keep their glyphs at private-use codepoints of their own rather than
+//! SMuFL's, so are mapped by name instead.
+
+use sfff::STAVE_SPACE;
+use svgdom::Path;
+
+use crate::glyphnames::parse_codepoint;
+use crate::outline;
+use crate::smufl_serde::SMuFLMetadata;
+
+/// LilyPond glyph names, with the SMuFL canonical name and codepoint of the
+/// glyph each is drawn as.
+pub const GLYPHS: &[(&str, &str, u16)] = &[
+    // Rests
+    ("rests.M3", "restMaxima", 0xE4E0),
+    ("rests.M2", "restLonga", 0xE4E1),
+    ("rests.M1", "restDoubleWhole", 0xE4E2),
+    ("rests.M1o", "restDoubleWholeLegerLine", 0xE4F3),
+    ("rests.0", "restWhole", 0xE4E3),
+    ("rests.0o", "restWholeLegerLine", 0xE4F4),
+    ("rests.1", "restHalf", 0xE4E4),
+    ("rests.1o", "restHalfLegerLine", 0xE4F5),
+    ("rests.2", "restQuarter", 0xE4E5),
+    ("rests.2classical", "restQuarterOld", 0xE4F2),
+    ("rests.3", "rest8th", 0xE4E6),
+    ("rests.4", "rest16th", 0xE4E7),
+    ("rests.5", "rest32nd", 0xE4E8),
+    ("rests.6", "rest64th", 0xE4E9),
+    ("rests.7", "rest128th", 0xE4EA),
+    // Noteheads
+    ("noteheads.sM1", "noteheadDoubleWhole", 0xE0A0),
+    ("noteheads.sM1double", "noteheadDoubleWholeSquare", 0xE0A1),
+    ("noteheads.s0", "noteheadWhole", 0xE0A2),
+    ("noteheads.s1", "noteheadHalf", 0xE0A3),
+    ("noteheads.s2", "noteheadBlack", 0xE0A4),
+    ("noteheads.s0cross", "noteheadXWhole", 0xE0A7),
+    ("noteheads.s1cross", "noteheadXHalf", 0xE0A8),
+    ("noteheads.s2cross", "noteheadXBlack", 0xE0A9),
+    ("noteheads.s2xcircle", "noteheadCircleX", 0xE0B3),
+    ("noteheads.s0triangle", "noteheadTriangleUpWhole", 0xE0BB),
+    ("noteheads.u1triangle", "noteheadTriangleUpHalf", 0xE0BC),
+    ("noteheads.u2triangle", "noteheadTriangleUpBlack", 0xE0BE),
+    ("noteheads.s0diamond", "noteheadDiamondWhole", 0xE0D8),
+    ("noteheads.s1diamond", "noteheadDiamondHalf", 0xE0D9),
+    ("noteheads.s2diamond", "noteheadDiamondBlack", 0xE0DB),
+    ("noteheads.s2slash", "noteheadSlashHorizontalEnds", 0xE101),
+    ("noteheads.s0slash", "noteheadSlashWhiteWhole", 0xE102),
+    ("noteheads.s1slash", "noteheadSlashWhiteHalf", 0xE103),
+    // Flags
+    ("flags.u3", "flag8thUp", 0xE240),
+    ("flags.d3", "flag8thDown", 0xE241),
+    ("flags.u4", "flag16thUp", 0xE242),
+    ("flags.d4", "flag16thDown", 0xE243),
+    ("flags.u5", "flag32ndUp", 0xE244),
+    ("flags.d5", "flag32ndDown", 0xE245),
+    ("flags.u6", "flag64thUp", 0xE246),
+    ("flags.d6", "flag64thDown", 0xE247),
+    ("flags.u7", "flag128thUp", 0xE248),
+    ("flags.d7", "flag128thDown", 0xE249),
+    ("flags.ugrace", "graceNoteSlashStemUp", 0xE564),
+    ("flags.dgrace", "graceNoteSlashStemDown", 0xE565),
+    // Accidentals
+    ("accidentals.flat", "accidentalFlat", 0xE260),
+    ("accidentals.natural", "accidentalNatural", 0xE261),
+    ("accidentals.sharp", "accidentalSharp", 0xE262),
+    ("accidentals.doublesharp", "accidentalDoubleSharp", 0xE263),
+    ("accidentals.flatflat", "accidentalDoubleFlat", 0xE264),
+    ("accidentals.leftparen", "accidentalParensLeft", 0xE26A),
+    ("accidentals.rightparen", "accidentalParensRight", 0xE26B),
+    ("accidentals.flat.arrowup", "accidentalQuarterToneFlatArrowUp", 0xE270),
+    ("accidentals.flat.arrowdown", "accidentalThreeQuarterTonesFlatArrowDown", 0xE271),
+    ("accidentals.natural.arrowup", "accidentalQuarterToneSharpNaturalArrowUp", 0xE272),
+    ("accidentals.natural.arrowdown", "accidentalQuarterToneFlatNaturalArrowDown", 0xE273),
+    ("accidentals.sharp.arrowup", "accidentalThreeQuarterTonesSharpArrowUp", 0xE274),
+    ("accidentals.sharp.arrowdown", "accidentalQuarterToneSharpArrowDown", 0xE275),
+    ("accidentals.mirroredflat", "accidentalQuarterToneFlatStein", 0xE280),
+    ("accidentals.mirroredflat.flat", "accidentalThreeQuarterTonesFlatZimmermann", 0xE281),
+    ("accidentals.sharp.slashslash.stem", "accidentalQuarterToneSharpStein", 0xE282),
+    ("accidentals.sharp.slashslashslash.stemstem", "accidentalThreeQuarterTonesSharpStein", 0xE283),
+    // Augmentation dot
+    ("dots.dot", "augmentationDot", 0xE1E7),
+    // Clefs
+    ("clefs.G", "gClef", 0xE050),
+    ("clefs.C", "cClef", 0xE05C),
+    ("clefs.F", "fClef", 0xE062),
+    ("clefs.percussion", "unpitchedPercussionClef1", 0xE069),
+    ("clefs.tab", "6stringTabClef", 0xE06D),
+    ("clefs.G_change", "gClefChange", 0xE07A),
+    ("clefs.C_change", "cClefChange", 0xE07B),
+    ("clefs.F_change", "fClefChange", 0xE07C),
+    // Time signatures
+    ("timesig.C44", "timeSigCommon", 0xE08A),
+    ("timesig.C22", "timeSigCutCommon", 0xE08B),
+    ("zero", "timeSig0", 0xE080),
+    ("one", "timeSig1", 0xE081),
+    ("two", "timeSig2", 0xE082),
+    ("three", "timeSig3", 0xE083),
+    ("four", "timeSig4", 0xE084),
+    ("five", "timeSig5", 0xE085),
+    ("six", "timeSig6", 0xE086),
+    ("seven", "timeSig7", 0xE087),
+    ("eight", "timeSig8", 0xE088),
+    ("nine", "timeSig9", 0xE089),
+    ("plus", "timeSigPlus", 0xE08C),
+    // Articulations, ornaments and other scripts
+    ("scripts.ufermata", "fermataAbove", 0xE4C0),
+    ("scripts.dfermata", "fermataBelow", 0xE4C1),
+    ("scripts.ushortfermata", "fermataShortAbove", 0xE4C4),
+    ("scripts.dshortfermata", "fermataShortBelow", 0xE4C5),
+    ("scripts.ulongfermata", "fermataLongAbove", 0xE4C6),
+    ("scripts.dlongfermata", "fermataLongBelow", 0xE4C7),
+    ("scripts.uverylongfermata", "fermataVeryLongAbove", 0xE4C8),
+    ("scripts.dverylongfermata", "fermataVeryLongBelow", 0xE4C9),
+    ("scripts.thumb", "stringsThumbPosition", 0xE624),
+    ("scripts.sforzato", "articAccentAbove", 0xE4A0),
+    ("scripts.staccato", "articStaccatoAbove", 0xE4A2),
+    ("scripts.tenuto", "articTenutoAbove", 0xE4A4),
+    ("scripts.uportato", "articTenutoStaccatoAbove", 0xE4B2),
+    ("scripts.dportato", "articTenutoStaccatoBelow", 0xE4B3),
+    ("scripts.umarcato", "articMarcatoAbove", 0xE4AC),
+    ("scripts.dmarcato", "articMarcatoBelow", 0xE4AD),
+    ("scripts.open", "brassMuteOpen", 0xE5E7),
+    ("scripts.halfopen", "brassMuteHalfClosed", 0xE5E6),
+    ("scripts.stopped", "brassMuteClosed", 0xE5E5),
+    ("scripts.upbow", "stringsUpBow", 0xE612),
+    ("scripts.downbow", "stringsDownBow", 0xE610),
+    ("scripts.turn", "ornamentTurn", 0xE567),
+    ("scripts.reverseturn", "ornamentTurnInverted", 0xE568),
+    ("scripts.trill", "ornamentTrill", 0xE566),
+    ("scripts.flageolet", "stringsHarmonic", 0xE614),
+    ("scripts.segno", "segno", 0xE047),
+    ("scripts.coda", "coda", 0xE048),
+    ("scripts.varcoda", "codaSquare", 0xE049),
+    ("scripts.rcomma", "breathMarkComma", 0xE4CE),
+    ("scripts.tickmark", "breathMarkTick", 0xE4CF),
+    ("scripts.caesura.straight", "caesura", 0xE4D1),
+    ("scripts.caesura.curved", "caesuraCurved", 0xE4D4),
+    ("scripts.trill_element", "wiggleTrill", 0xEAA4),
+    ("scripts.prall", "ornamentShortTrill", 0xE56C),
+    ("scripts.mordent", "ornamentMordent", 0xE56D),
+    ("scripts.prallprall", "ornamentTremblement", 0xE56E),
+    ("scripts.snappizzicato", "pluckedSnapPizzicatoAbove", 0xE631),
+    // Dynamics
+    ("p", "dynamicPiano", 0xE520),
+    ("m", "dynamicMezzo", 0xE521),
+    ("f", "dynamicForte", 0xE522),
+    ("r", "dynamicRinforzando", 0xE523),
+    ("s", "dynamicSforzando", 0xE524),
+    ("z", "dynamicZ", 0xE525),
+    // Pedals
+    ("pedal.Ped", "keyboardPedalPed", 0xE650),
+    ("pedal.P", "keyboardPedalP", 0xE651),
+    ("pedal.e", "keyboardPedalE", 0xE652),
+    ("pedal.d", "keyboardPedalD", 0xE653),
+    ("pedal..", "keyboardPedalDot", 0xE654),
+    ("pedal.*", "keyboardPedalUp", 0xE655),
+    // Bracket ends
+    ("brackettips.up", "bracketTop", 0xE003),
+    ("brackettips.down", "bracketBottom", 0xE004),
+];
+
+/// The SMuFL brace, which brace fonts' braces are fitted to along with its
+/// alternates.
+const BRACE: (&str, u16) = ("brace", 0xE000);
+
+/// SMuFL codepoint of the glyph with a LilyPond `name`.
+pub fn codepoint(name: &str) -> Option<u16> {
+    GLYPHS.iter().find(|(lilypond, _, _)| *lilypond == name).map(|(_, _, cp)| *cp)
+}
+
+/// Whether `name` is one of a LilyPond brace font's braces, which go from
+/// `brace0` (the shortest) to `brace575`.
+pub fn is_brace(name: &str) -> bool {
+    name.starts_with("brace") && name["brace".len()..].parse::<u16>().is_ok()
+}
+
+/// Give a brace font's `braces` the codepoints of the SMuFL brace and the
+/// alternates `metadata` lists for it.  LilyPond has a brace drawn for every
+/// size, where SMuFL fonts have one to scale (and alternates for bigger or
+/// smaller systems), so each gets the brace with the nearest proportions to
+/// its bounding box, scaled to fill it.  Returns each fitted brace's
+/// codepoint, the brace's codepoint if it's an alternate, and outline.
+pub fn fit_braces(braces: &[Path], metadata: &SMuFLMetadata) -> Vec<(u16, Option<u16>, Path)> {
+    let (brace, brace_cp) = BRACE;
+    let mut targets = vec![(brace, brace_cp, None)];
+    if let Some(alternates) = metadata.glyphsWithAlternates.get(brace) {
+        for alt in alternates.alternates.iter() {
+            if let Some(cp) = parse_codepoint(&alt.codepoint) {
+                targets.push((alt.name.as_str(), cp, Some(brace_cp)));
+            }
+        }
+    }
+
+    let sizes: Vec<(&Path, [f64; 4])> = braces
+        .iter()
+        .filter_map(|path| Some((path, outline::extent(path)?)))
+        .filter(|(_, [_, y0, _, y1])| y1 > y0)
+        .collect();
+    let ratio = |[x0, y0, x1, y1]: [f64; 4]| (x1 - x0) / (y1 - y0);
+
+    let mut fitted = Vec::new();
+    for (name, codepoint, base) in targets {
+        let bbox = match metadata.glyphBBoxes.get(name) {
+            Some(bbox) => bbox,
+            None => continue,
+        };
+        let ss = STAVE_SPACE as f64;
+        let target = [
+            f64::from(bbox.bBoxSW[0]) * ss,
+            f64::from(bbox.bBoxSW[1]) * ss,
+            f64::from(bbox.bBoxNE[0]) * ss,
+            f64::from(bbox.bBoxNE[1]) * ss,
+        ];
+        if target[3] <= target[1] {
+            continue;
+        }
+        let nearest = sizes.iter().min_by(|(_, a), (_, b)| {
+            let off = |extent| (ratio(extent) / ratio(target)).ln().abs();
+            off(*a).total_cmp(&off(*b))
+        });
+        if let Some((path, [x0, y0, _, y1])) = nearest {
+            let scale = (target[3] - target[1]) / (y1 - y0);
+            let dx = target[0] - x0 * scale;
+            let dy = target[1] - y0 * scale;
+            fitted.push((codepoint, base, outline::transform(path, scale, dx, dy)));
+        }
+    }
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{bravura, has_bbox};
+
+    #[test]
+    fn names_match_bravura() {
+        let bravura = bravura();
+        for (lilypond, name, cp) in GLYPHS.iter() {
+            assert!(has_bbox(&bravura, name, *cp), "{} isn't {} at {:04X}", lilypond, name, cp);
+        }
+    }
+
+    #[test]
+    fn codepoints_by_name() {
+        assert_eq!(codepoint("rests.0"), Some(0xE4E3));
+        assert_eq!(codepoint("noteheads.s2"), Some(0xE0A4));
+        assert_eq!(codepoint("noteheads.s2re"), None);
+        assert!(is_brace("brace0"));
+        assert!(is_brace("brace575"));
+        assert!(!is_brace("brace"));
+        assert!(!is_brace("braceSmall"));
+    }
+}
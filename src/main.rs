@@ -4,12 +4,41 @@ use std::collections::HashMap;
 
 use sfff::{Glyph, GlyphsBuilder, SfFontMetadata, STAVE_SPACE};
 use std::fmt::Write;
-use svgdom::{AttributeId, AttributeValue, ElementId, FilterSvg, Document, Path};
-use serde_json as json;
+use svgdom::Path;
 
 use Glyph::*;
 
-use smufl_serde::{SMuFLMetadata, GlyphWithAnchors};
+use classes::Classes;
+use extra::Extras;
+use glyphnames::GlyphNames;
+use ranges::Ranges;
+use source::{Layered, Source};
+
+mod canvas;
+mod classes;
+mod composite;
+mod compress;
+mod coverage;
+mod css;
+mod esm;
+mod extra;
+mod font_json;
+mod glyphnames;
+mod large;
+mod lilypond;
+mod optical;
+mod otf;
+mod outline;
+mod overlap;
+mod pdf;
+mod profile;
+mod ranges;
+mod source;
+mod sprites;
+mod subset;
+mod unicode;
+mod vfont;
+mod weight;
 
 mod smufl_serde {
     #![allow(non_snake_case)]
@@ -243,6 +272,24 @@ mod smufl_serde {
         pub codepoint: String,
     }
 
+    /// An entry of SMuFL's `glyphnames.json`, keyed by canonical glyph name.
+    #[derive(Serialize, Deserialize)]
+    pub struct GlyphName {
+        pub codepoint: String,
+        /// The equivalent codepoint in the Unicode Musical Symbols range, if any.
+        pub alternateCodepoint: Option<String>,
+        pub description: String,
+    }
+
+    /// An entry of SMuFL's `ranges.json`, keyed by range name.
+    #[derive(Serialize, Deserialize)]
+    pub struct Range {
+        pub description: String,
+        pub glyphs: Vec<String>,
+        pub range_start: String,
+        pub range_end: String,
+    }
+
     /// JSON Metadata for SMuFL format
     #[derive(Serialize, Deserialize)]
     pub struct SMuFLMetadata {
@@ -296,12 +343,17 @@ mod smufl_serde {
     }
 }
 
-fn path(font: &(HashMap<u16, Path>, f64), id: u16) -> String {
+fn path(font: &HashMap<u16, Path>, id: u16) -> String {
+    svg_path(font.get(&id).unwrap())
+}
+
+/// Write a path scaled to ScoreFall units as a compact SVG path string, with
+/// the y axis flipped to point down.
+fn svg_path(path: &Path) -> String {
     let mut output = "".to_string();
 
-    let path: svgdom::Path = font.0.get(&id).unwrap().clone();
-    let convert = |x: f64| (x * font.1).round();
-    let convert_y = |y: f64| (y * -font.1).round();
+    let convert = |x: f64| x.round();
+    let convert_y = |y: f64| (-y).round();
 
     for i in path.iter() {
         use svgdom::PathSegment::*;
@@ -448,7 +500,7 @@ fn path(font: &(HashMap<u16, Path>, f64), id: u16) -> String {
             } => {
                 let rx = convert(rx);
                 let ry = convert_y(ry);
-                let x_axis_rotation = convert(x_axis_rotation);
+                let x_axis_rotation = x_axis_rotation.round();
                 let x = convert(x);
                 let y = convert_y(y);
                 let cmd = if abs { "A" } else { "a" };
@@ -486,209 +538,427 @@ fn path(font: &(HashMap<u16, Path>, f64), id: u16) -> String {
     output
 }
 
+/// Name of a `Glyph` variant in lower camel case, as used for identifiers in
+/// the web outputs: `ClefG` => `clefG`, `MP` => `mp`.
+fn lower_camel(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut output = String::with_capacity(name.len());
+    for (i, c) in chars.iter().enumerate() {
+        // Lowercase the leading capitals, except the start of the next word.
+        let leading = chars[..=i].iter().all(|c| c.is_ascii_uppercase());
+        let next_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+        if leading && (i == 0 || !next_lower) {
+            output.push(c.to_ascii_lowercase());
+        } else {
+            output.push(*c);
+        }
+    }
+    output
+}
+
+/// Expand `(Variant, SMuFL name, codepoint)` to `(Glyph, variant name, SMuFL
+/// name, codepoint)`.
+macro_rules! glyphs {
+    ($(($glyph:ident, $smufl:expr, $cp:expr)),* $(,)?) => {
+        vec![$(($glyph, stringify!($glyph), $smufl, $cp)),*]
+    };
+}
+
+/// ScoreFall glyphs, their names, and the SMuFL names and codepoints of the
+/// glyphs they are taken from.
+fn glyph_table() -> Vec<(Glyph, &'static str, &'static str, u16)> {
+    glyphs![
+        // Notehead IDS from https://w3c.github.io/smufl/gitbook/tables/noteheads.html
+        (NoteheadFill, "noteheadBlack", 0xE0A4),
+        (NoteheadHalf, "noteheadHalf", 0xE0A3),
+        (NoteheadWhole, "noteheadWhole", 0xE0A2),
+        (NoteheadDouble, "noteheadDoubleWhole", 0xE0A0),
+        (NoteheadFillX, "noteheadXBlack", 0xE0A9),
+        (NoteheadHalfX, "noteheadXHalf", 0xE0A8),
+        (NoteheadWholeX, "noteheadXWhole", 0xE0A7),
+        (NoteheadDoubleX, "noteheadXDoubleWhole", 0xE0A6),
+        (NoteheadFillTriangle, "noteheadTriangleUpBlack", 0xE0BE),
+        (NoteheadHalfTriangle, "noteheadTriangleUpHalf", 0xE0BC),
+        (NoteheadWholeTriangle, "noteheadTriangleUpWhole", 0xE0BB),
+        (NoteheadDoubleTriangle, "noteheadTriangleUpDoubleWhole", 0xE0BA),
+        (NoteheadFillDiamond, "noteheadDiamondBlack", 0xE0DB),
+        (NoteheadHalfDiamond, "noteheadDiamondHalf", 0xE0D9),
+        (NoteheadWholeDiamond, "noteheadDiamondWhole", 0xE0D8),
+        (NoteheadDoubleDiamond, "noteheadDiamondDoubleWhole", 0xE0D7),
+        (NoteheadFillSlash, "noteheadSlashHorizontalEnds", 0xE101),
+        (NoteheadHalfSlash, "noteheadSlashWhiteHalf", 0xE103),
+        (NoteheadWholeSlash, "noteheadSlashWhiteWhole", 0xE102),
+        (NoteheadDoubleSlash, "noteheadSlashWhiteDoubleWhole", 0xE10A),
+        (NoteheadFillSlashed, "noteheadSlashedBlack2", 0xE0D0),
+        (NoteheadHalfSlashed, "noteheadSlashedHalf2", 0xE0D2),
+        (NoteheadWholeSlashed, "noteheadSlashedWhole2", 0xE0D4),
+        (NoteheadDoubleSlashed, "noteheadSlashedDoubleWhole2", 0xE0D6),
+        // Accidental IDs from https://w3c.github.io/smufl/gitbook/tables/standard-accidentals-12-edo.html
+        (Flat, "accidentalFlat", 0xE260),
+        (Sharp, "accidentalSharp", 0xE262),
+        (Natural, "accidentalNatural", 0xE261),
+        (DoubleFlat, "accidentalDoubleFlat", 0xE264),
+        (DoubleSharp, "accidentalDoubleSharp", 0xE263),
+        // https://w3c.github.io/smufl/gitbook/tables/stein-zimmermann-accidentals-24-edo.html
+        (QuarterFlat, "accidentalQuarterToneFlatStein", 0xE280),
+        (QuarterSharp, "accidentalQuarterToneSharpStein", 0xE282),
+        (ThreeQuarterFlat, "accidentalThreeQuarterTonesFlatZimmermann", 0xE281),
+        (ThreeQuarterSharp, "accidentalThreeQuarterTonesSharpStein", 0xE283),
+        (ThirdFlat, "accidentalOneThirdToneFlatFerneyhough", 0xE48B),
+        (ThirdSharp, "accidentalOneThirdToneSharpFerneyhough", 0xE48A),
+        (TwoThirdFlat, "accidentalTwoThirdTonesFlatFerneyhough", 0xE48D),
+        (TwoThirdSharp, "accidentalTwoThirdTonesSharpFerneyhough", 0xE48C),
+        (FlagUp8, "flag8thUp", 0xE240),
+        (FlagDown8, "flag8thDown", 0xE241),
+        (FlagUp16, "flag16thUp", 0xE242),
+        (FlagDown16, "flag16thDown", 0xE243),
+        (FlagUp32, "flag32ndUp", 0xE244),
+        (FlagDown32, "flag32ndDown", 0xE245),
+        (FlagUp64, "flag64thUp", 0xE246),
+        (FlagDown64, "flag64thDown", 0xE247),
+        (RestMulti, "restHBar", 0xE4EE),
+        (Rest1, "restWhole", 0xE4E3),
+        (Rest2, "restHalf", 0xE4E4),
+        (Rest4, "restQuarter", 0xE4E5),
+        (Rest8, "rest8th", 0xE4E6),
+        (Rest16, "rest16th", 0xE4E7),
+        (Rest32, "rest32nd", 0xE4E8),
+        (Rest64, "rest64th", 0xE4E9),
+        (ClefC, "cClef", 0xE05C),
+        (ClefG, "gClef", 0xE050),
+        (ClefF, "fClef", 0xE062),
+        (ClefN, "unpitchedPercussionClef1", 0xE069),
+        (Clef8, "clef8", 0xE07D),
+        (Clef15, "clef15", 0xE07E),
+        (Tab4, "4stringTabClef", 0xE06E),
+        (Tab6, "6stringTabClef", 0xE06D),
+        (P, "dynamicPiano", 0xE520),
+        (MP, "dynamicMP", 0xE52C),
+        (MF, "dynamicMF", 0xE52D),
+        (F, "dynamicForte", 0xE522),
+        (S, "dynamicSforzando", 0xE524),
+        (Z, "dynamicZ", 0xE525),
+        (N, "dynamicNiente", 0xE526),
+        (TimeSig0, "timeSig0", 0xE080),
+        (TimeSig1, "timeSig1", 0xE081),
+        (TimeSig2, "timeSig2", 0xE082),
+        (TimeSig3, "timeSig3", 0xE083),
+        (TimeSig4, "timeSig4", 0xE084),
+        (TimeSig5, "timeSig5", 0xE085),
+        (TimeSig6, "timeSig6", 0xE086),
+        (TimeSig7, "timeSig7", 0xE087),
+        (TimeSig8, "timeSig8", 0xE088),
+        (TimeSig9, "timeSig9", 0xE089),
+        (TimeSigCommon, "timeSigCommon", 0xE08A),
+        (TimeSigCut, "timeSigCutCommon", 0xE08B),
+        (TimeSigPlus, "timeSigPlus", 0xE08C),
+        (RepeatSlash, "repeatBarSlash", 0xE504),
+        (RepeatUpDot, "repeatBarUpperDot", 0xE503),
+        (RepeatDownDot, "repeatBarLowerDot", 0xE505),
+        (TupletColon, "tupletColon", 0xE88A),
+        (Tuplet0, "tuplet0", 0xE880),
+        (Tuplet1, "tuplet1", 0xE881),
+        (Tuplet2, "tuplet2", 0xE882),
+        (Tuplet3, "tuplet3", 0xE883),
+        (Tuplet4, "tuplet4", 0xE884),
+        (Tuplet5, "tuplet5", 0xE885),
+        (Tuplet6, "tuplet6", 0xE886),
+        (Tuplet7, "tuplet7", 0xE887),
+        (Tuplet8, "tuplet8", 0xE888),
+        (Tuplet9, "tuplet9", 0xE889),
+        (Coda, "coda", 0xE048),
+        (Segno, "segno", 0xE047),
+        (BuzzRoll, "stemBuzzRoll", 0xE217),
+        (Damp, "stemDamp", 0xE218),
+        (HarpStringNoise, "stemHarpStringNoise", 0xE21F),
+        (RimShot, "stemRimShot", 0xE21E),
+        (BowBridge, "stemBowOnBridge", 0xE215),
+        (BowTailpiece, "stemBowOnTailpiece", 0xE216),
+        (Tremelo1, "tremolo1", 0xE220),
+        (Tremelo2, "tremolo2", 0xE221),
+        (Tremelo3, "tremolo3", 0xE222),
+        (Tremelo4, "tremolo4", 0xE223),
+        (Tremelo5, "tremolo5", 0xE224),
+    ]
+}
+
+/// Exit with a message if SMuFL's `glyphnames.json` wasn't `loaded`, for an
+/// `option` that finds glyphs by their SMuFL name.
+fn need_glyphnames(loaded: bool, option: &str) {
+    if !loaded {
+        eprintln!("{} needs SMuFL's glyphnames.json from --glyphnames", option);
+        std::process::exit(1);
+    }
+}
+
+fn usage(executable: &str) -> ! {
+    eprintln!("Usage: {} coverage report font.svg meta.json ...", executable);
+    eprintln!("Usage: {} import font.json output.sfff", executable);
+    eprintln!(
+        "Usage: {} output.sfff font.svg meta.json \
+         [--override font.svg meta.json]... [--supplement font.svg]... \
+         [--glyphnames glyphnames.json] [--ligatures] [--alternates] [--set type]... \
+         [--small scale] [--optional class]... [--classes classes.json] [--class class]... \
+         [--ranges ranges.json] [--range range]... [--unicode] [--profile] \
+         [--composites composites.json] [--large-time-sigs scale] \
+         [--weight amount] [--class-weight class amount]... \
+         [--remove-overlaps] [--vfont dir] [--esm dir] [--json font.json] \
+         [--sprites sprites.svg] [--css glyphs.css] [--otf font.otf] \
+         [--subset glyphs.txt] [--compress] [--pdf font.pdf] \
+         [--canvas dir]",
+        executable
+    );
+    std::process::exit(1);
+}
+
 fn main() {
     let mut args = std::env::args();
     let executable = args.next().unwrap();
-    let out = args.next().unwrap_or_else(|| {
-        eprintln!("Usage: {} output.sfff font.svg meta.json", executable);
-        std::process::exit(1);
-    });
-    let svg = args.next().unwrap_or_else(|| {
-        eprintln!("Usage: {} output.sfff font.svg meta.json", executable);
-        std::process::exit(1);
-    });
-    let meta = args.next().unwrap_or_else(|| {
-        eprintln!("Usage: {} output.sfff font.svg meta.json", executable);
-        std::process::exit(1);
-    });
-    let svg_data = std::fs::read_to_string(&svg).unwrap_or_else(|_| {
-        eprintln!("No such file: {}", svg);
-        std::process::exit(1);
-    });
-    let metadata = std::fs::read_to_string(&meta).unwrap_or_else(|_| {
-        eprintln!("No such file: {}", meta);
-        std::process::exit(1);
-    });
-    let font = Document::from_str(&svg_data).unwrap_or_else(|_| {
-        eprintln!("Failed to load font file!");
-        std::process::exit(1);
-    });
-
-    // Build hashmap of svg font document paths.
-    let iter = font.root().descendants().svg();
-    let mut scalar = None;
-    let mut font = HashMap::new();
-    for (id, node) in iter {
-        let attrs = node.attributes();
-        match id {
-            ElementId::FontFace => {
-                // Get units per "em" (Actually units per 5 line stave).
-                let upe = if let Some(&AttributeValue::String(ref upe)) =
-                    attrs.get_value(AttributeId::UnitsPerEm)
-                {
-                    upe.parse::<f64>().unwrap()
-                } else {
-                    panic!("Wrong type for font-face/units-per-em");
-                };
-                // Get units per "ss" (Stave Space).
-                let upss = upe * 0.25;
-                // Calculate scalar value for paths.
-                scalar = Some(STAVE_SPACE as f64 / upss);
-            }
-            ElementId::Glyph => {
-                // Glyph needs a name and path.
-                let name = if let Some(&AttributeValue::String(ref name)) =
-                    attrs.get_value(AttributeId::GlyphName)
-                {
-                    name
-                } else {
-                    continue;
-                };
-                let path = if let Some(&AttributeValue::Path(ref path)) =
-                    attrs.get_value(AttributeId::D)
-                {
-                    path.clone()
-                } else {
-                    continue;
-                };
-                let name = if name.starts_with("uni") {
-                    name.trim_start_matches("uni")
-                } else {
-                    continue;
-                };
-                if name.contains("uni") || name.contains(".") {
-                    // Ignore glyphs for multiple unicode combined or alternate.
-                    continue;
-                }
-                let name = u16::from_str_radix(name, 16).unwrap();
+    let out = args.next().unwrap_or_else(|| usage(&executable));
+    if out == "coverage" {
+        coverage::run(&executable, args);
+        return;
+    }
+    if out == "import" {
+        font_json::run(&executable, args);
+        return;
+    }
+    let svg = args.next().unwrap_or_else(|| usage(&executable));
+    let meta = args.next().unwrap_or_else(|| usage(&executable));
 
-                font.insert(name, path);
+    let mut font = Layered::new(Source::load(&svg, &meta));
+    let mut names = GlyphNames::bundled();
+    let mut names_loaded = false;
+    let mut ligatures = false;
+    let mut alternates = false;
+    let mut set_types = Vec::new();
+    let mut small = None;
+    let mut optional_classes = Vec::new();
+    let mut smufl_classes = None;
+    let mut classes = Vec::new();
+    let mut smufl_ranges = None;
+    let mut ranges = Vec::new();
+    let mut unicode = false;
+    let mut profile = false;
+    let mut composites = Vec::new();
+    let mut large_time_sigs = None;
+    let mut weight = None;
+    let mut class_weights = Vec::new();
+    let mut remove_overlaps = false;
+    let mut vfont_dir = None;
+    let mut esm_dir = None;
+    let mut canvas_dir = None;
+    let mut json_file = None;
+    let mut sprites_file = None;
+    let mut css_file = None;
+    let mut otf_file = None;
+    let mut pdf_file = None;
+    let mut subset = None;
+    let mut compress = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--override" => {
+                let svg = args.next().unwrap_or_else(|| usage(&executable));
+                let meta = args.next().unwrap_or_else(|| usage(&executable));
+                font.overlay(Source::load(&svg, &meta));
+            }
+            "--supplement" => {
+                let svg = args.next().unwrap_or_else(|| usage(&executable));
+                font.supplement(Source::load_svg(&svg));
+            }
+            "--glyphnames" => {
+                let file = args.next().unwrap_or_else(|| usage(&executable));
+                names = GlyphNames::load(&file);
+                names_loaded = true;
+            }
+            "--ligatures" => ligatures = true,
+            "--alternates" => alternates = true,
+            "--set" => set_types.push(args.next().unwrap_or_else(|| usage(&executable))),
+            "--optional" => {
+                optional_classes.push(args.next().unwrap_or_else(|| usage(&executable)));
             }
-            ElementId::Hkern => {
-//                println!("Hkern");
+            "--classes" => {
+                let file = args.next().unwrap_or_else(|| usage(&executable));
+                smufl_classes = Some(Classes::load(&file));
             }
-            _ => println!("{}", id),
+            "--class" => classes.push(args.next().unwrap_or_else(|| usage(&executable))),
+            "--ranges" => {
+                let file = args.next().unwrap_or_else(|| usage(&executable));
+                smufl_ranges = Some(Ranges::load(&file));
+            }
+            "--range" => ranges.push(args.next().unwrap_or_else(|| usage(&executable))),
+            "--unicode" => unicode = true,
+            "--profile" => profile = true,
+            "--composites" => {
+                let file = args.next().unwrap_or_else(|| usage(&executable));
+                composites.extend(composite::load(&file));
+            }
+            "--large-time-sigs" => {
+                let scale = args.next().unwrap_or_else(|| usage(&executable));
+                let scale = scale.parse::<f32>().unwrap_or_else(|_| usage(&executable));
+                large_time_sigs = Some(scale);
+            }
+            "--weight" => {
+                let amount = args.next().unwrap_or_else(|| usage(&executable));
+                weight = Some(amount.parse::<f32>().unwrap_or_else(|_| usage(&executable)));
+            }
+            "--class-weight" => {
+                let class = args.next().unwrap_or_else(|| usage(&executable));
+                let amount = args.next().unwrap_or_else(|| usage(&executable));
+                let amount = amount.parse::<f32>().unwrap_or_else(|_| usage(&executable));
+                class_weights.push((class, amount));
+            }
+            "--remove-overlaps" => remove_overlaps = true,
+            "--compress" => compress = true,
+            "--vfont" => vfont_dir = Some(args.next().unwrap_or_else(|| usage(&executable))),
+            "--esm" => esm_dir = Some(args.next().unwrap_or_else(|| usage(&executable))),
+            "--canvas" => canvas_dir = Some(args.next().unwrap_or_else(|| usage(&executable))),
+            "--json" => json_file = Some(args.next().unwrap_or_else(|| usage(&executable))),
+            "--css" => css_file = Some(args.next().unwrap_or_else(|| usage(&executable))),
+            "--otf" => otf_file = Some(args.next().unwrap_or_else(|| usage(&executable))),
+            "--pdf" => pdf_file = Some(args.next().unwrap_or_else(|| usage(&executable))),
+            "--subset" => {
+                let file = args.next().unwrap_or_else(|| usage(&executable));
+                subset = Some(subset::load(&file));
+            }
+            "--sprites" => {
+                sprites_file = Some(args.next().unwrap_or_else(|| usage(&executable)));
+            }
+            "--small" => {
+                let scale = args.next().unwrap_or_else(|| usage(&executable));
+                small = Some(scale.parse::<f32>().unwrap_or_else(|_| usage(&executable)));
+            }
+            _ => usage(&executable),
         }
     }
-    let font = (font, scalar.unwrap());
+
+    names.add_metadata(&font.metadata);
+    let built = composite::build(&mut font, &names, &composites);
+    if profile {
+        profile::apply(&mut font);
+    }
+    if let Some(scale) = large_time_sigs {
+        let synthesized = large::synthesize(&mut font, scale);
+        println!("Synthesized {} large time signature digits", synthesized);
+        if !set_types.iter().any(|t| t == large::SET_TYPE) {
+            set_types.push(large::SET_TYPE.to_string());
+        }
+    }
+    if small.is_some() {
+        let replaced = optical::use_small_variants(&mut font, &names);
+        println!("Using {} small optical variants", replaced);
+    }
+    for (class, amount) in class_weights.iter() {
+        need_glyphnames(names_loaded, "--class-weight");
+        let smufl_classes = smufl_classes.as_ref().unwrap_or_else(|| {
+            eprintln!("--class-weight needs SMuFL's classes.json from --classes");
+            std::process::exit(1);
+        });
+        let codepoints: Vec<u16> = smufl_classes
+            .members(class)
+            .iter()
+            .filter_map(|name| names.codepoint(name))
+            .collect();
+        let changed = weight::apply(&mut font, &names, Some(&codepoints), *amount);
+        println!("Adjusted the weight of {} glyphs in {}", changed, class);
+    }
+    if let Some(amount) = weight {
+        weight::apply(&mut font, &names, None, amount);
+    }
+    if remove_overlaps {
+        for path in font.glyphs.values_mut().chain(font.ligatures.values_mut()) {
+            *path = overlap::normalize(path);
+        }
+    }
+
+    // Every `Glyph` needs an outline from one of the sources.
+    let missing: Vec<String> = glyph_table()
+        .iter()
+        .filter(|(_, _, _, cp)| !font.glyphs.contains_key(cp))
+        .map(|(_, name, _, cp)| format!("{:04X} ({})", cp, name))
+        .collect();
+    if !missing.is_empty() {
+        eprintln!("No source has {} glyphs: {}", missing.len(), missing.join(", "));
+        std::process::exit(1);
+    }
 
     // Build glyphs string.
     let mut glyphs = GlyphsBuilder::new();
-    // Notehead IDS from https://w3c.github.io/smufl/gitbook/tables/noteheads.html
-    glyphs.push(NoteheadFill, path(&font, 0xE0A4));
-    glyphs.push(NoteheadHalf, path(&font, 0xE0A3));
-    glyphs.push(NoteheadWhole, path(&font, 0xE0A2));
-    glyphs.push(NoteheadDouble, path(&font, 0xE0A0));
-    glyphs.push(NoteheadFillX, path(&font, 0xE0A9));
-    glyphs.push(NoteheadHalfX, path(&font, 0xE0A8));
-    glyphs.push(NoteheadWholeX, path(&font, 0xE0A7));
-    glyphs.push(NoteheadDoubleX, path(&font, 0xE0A6));
-    glyphs.push(NoteheadFillTriangle, path(&font, 0xE0BE));
-    glyphs.push(NoteheadHalfTriangle, path(&font, 0xE0BC));
-    glyphs.push(NoteheadWholeTriangle, path(&font, 0xE0BB));
-    glyphs.push(NoteheadDoubleTriangle, path(&font, 0xE0BA));
-    glyphs.push(NoteheadFillDiamond, path(&font, 0xE0DB));
-    glyphs.push(NoteheadHalfDiamond, path(&font, 0xE0D9));
-    glyphs.push(NoteheadWholeDiamond, path(&font, 0xE0D8));
-    glyphs.push(NoteheadDoubleDiamond, path(&font, 0xE0D7));
-    glyphs.push(NoteheadFillSlash, path(&font, 0xE101));
-    glyphs.push(NoteheadHalfSlash, path(&font, 0xE103));
-    glyphs.push(NoteheadWholeSlash, path(&font, 0xE102));
-    glyphs.push(NoteheadDoubleSlash, path(&font, 0xE10A));
-    glyphs.push(NoteheadFillSlashed, path(&font, 0xE0D0));
-    glyphs.push(NoteheadHalfSlashed, path(&font, 0xE0D2));
-    glyphs.push(NoteheadWholeSlashed, path(&font, 0xE0D4));
-    glyphs.push(NoteheadDoubleSlashed, path(&font, 0xE0D6));
-    // Accidental IDs from https://w3c.github.io/smufl/gitbook/tables/standard-accidentals-12-edo.html
-    glyphs.push(Flat, path(&font, 0xE260));
-    glyphs.push(Sharp, path(&font, 0xE262));
-    glyphs.push(Natural, path(&font, 0xE261));
-    glyphs.push(DoubleFlat, path(&font, 0xE264));
-    glyphs.push(DoubleSharp, path(&font, 0xE263));
-    // https://w3c.github.io/smufl/gitbook/tables/stein-zimmermann-accidentals-24-edo.html
-    glyphs.push(QuarterFlat, path(&font, 0xE280));
-    glyphs.push(QuarterSharp, path(&font, 0xE282));
-    glyphs.push(ThreeQuarterFlat, path(&font, 0xE281));
-    glyphs.push(ThreeQuarterSharp, path(&font, 0xE283));
-    glyphs.push(ThirdFlat, path(&font, 0xE48B));
-    glyphs.push(ThirdSharp, path(&font, 0xE48A));
-    glyphs.push(TwoThirdFlat, path(&font, 0xE48D));
-    glyphs.push(TwoThirdSharp, path(&font, 0xE48C));
-    glyphs.push(FlagUp8, path(&font, 0xE240));
-    glyphs.push(FlagDown8, path(&font, 0xE241));
-    glyphs.push(FlagUp16, path(&font, 0xE242));
-    glyphs.push(FlagDown16, path(&font, 0xE243));
-    glyphs.push(FlagUp32, path(&font, 0xE244));
-    glyphs.push(FlagDown32, path(&font, 0xE245));
-    glyphs.push(FlagUp64, path(&font, 0xE246));
-    glyphs.push(FlagDown64, path(&font, 0xE247));
-    glyphs.push(RestMulti, path(&font, 0xE4EE));
-    glyphs.push(Rest1, path(&font, 0xE4E3));
-    glyphs.push(Rest2, path(&font, 0xE4E4));
-    glyphs.push(Rest4, path(&font, 0xE4E5));
-    glyphs.push(Rest8, path(&font, 0xE4E6));
-    glyphs.push(Rest16, path(&font, 0xE4E7));
-    glyphs.push(Rest32, path(&font, 0xE4E8));
-    glyphs.push(Rest64, path(&font, 0xE4E9));
-    glyphs.push(ClefC, path(&font, 0xE05C));
-    glyphs.push(ClefG, path(&font, 0xE050));
-    glyphs.push(ClefF, path(&font, 0xE062));
-    glyphs.push(ClefN, path(&font, 0xE069));
-    glyphs.push(Clef8, path(&font, 0xE07D));
-    glyphs.push(Clef15, path(&font, 0xE07E));
-    glyphs.push(Tab4, path(&font, 0xE06E));
-    glyphs.push(Tab6, path(&font, 0xE06D));
-    glyphs.push(P, path(&font, 0xE520));
-    glyphs.push(MP, path(&font, 0xE52C));
-    glyphs.push(MF, path(&font, 0xE52D));
-    glyphs.push(F, path(&font, 0xE522));
-    glyphs.push(S, path(&font, 0xE524));
-    glyphs.push(Z, path(&font, 0xE525));
-    glyphs.push(N, path(&font, 0xE526));
-    glyphs.push(TimeSig0, path(&font, 0xE080));
-    glyphs.push(TimeSig1, path(&font, 0xE081));
-    glyphs.push(TimeSig2, path(&font, 0xE082));
-    glyphs.push(TimeSig3, path(&font, 0xE083));
-    glyphs.push(TimeSig4, path(&font, 0xE084));
-    glyphs.push(TimeSig5, path(&font, 0xE085));
-    glyphs.push(TimeSig6, path(&font, 0xE086));
-    glyphs.push(TimeSig7, path(&font, 0xE087));
-    glyphs.push(TimeSig8, path(&font, 0xE088));
-    glyphs.push(TimeSig9, path(&font, 0xE089));
-    glyphs.push(TimeSigCommon, path(&font, 0xE08A));
-    glyphs.push(TimeSigCut, path(&font, 0xE08B));
-    glyphs.push(TimeSigPlus, path(&font, 0xE08C));
-    glyphs.push(RepeatSlash, path(&font, 0xE504));
-    glyphs.push(RepeatUpDot, path(&font, 0xE503));
-    glyphs.push(RepeatDownDot, path(&font, 0xE505));
-    glyphs.push(TupletColon, path(&font, 0xE88A));
-    glyphs.push(Tuplet0, path(&font, 0xE880));
-    glyphs.push(Tuplet1, path(&font, 0xE881));
-    glyphs.push(Tuplet2, path(&font, 0xE882));
-    glyphs.push(Tuplet3, path(&font, 0xE883));
-    glyphs.push(Tuplet4, path(&font, 0xE884));
-    glyphs.push(Tuplet5, path(&font, 0xE885));
-    glyphs.push(Tuplet6, path(&font, 0xE886));
-    glyphs.push(Tuplet7, path(&font, 0xE887));
-    glyphs.push(Tuplet8, path(&font, 0xE888));
-    glyphs.push(Tuplet9, path(&font, 0xE889));
-    glyphs.push(Coda, path(&font, 0xE048));
-    glyphs.push(Segno, path(&font, 0xE047));
-    glyphs.push(BuzzRoll, path(&font, 0xE217));
-    glyphs.push(Damp, path(&font, 0xE218));
-    glyphs.push(HarpStringNoise, path(&font, 0xE21F));
-    glyphs.push(RimShot, path(&font, 0xE21E));
-    glyphs.push(BowBridge, path(&font, 0xE215));
-    glyphs.push(BowTailpiece, path(&font, 0xE216));
-    glyphs.push(Tremelo1, path(&font, 0xE220));
-    glyphs.push(Tremelo2, path(&font, 0xE221));
-    glyphs.push(Tremelo3, path(&font, 0xE222));
-    glyphs.push(Tremelo4, path(&font, 0xE223));
-    glyphs.push(Tremelo5, path(&font, 0xE224));
+    let mut used = Vec::new();
+    let mut paths = Vec::new();
+    let mut subset_size = 0;
+    for (glyph, name, _, cp) in glyph_table() {
+        let path = path(&font.glyphs, cp);
+        // Glyphs left out of a subset keep their place, with no outline.
+        if subset.as_ref().is_none_or(|subset| subset.contains(&name)) {
+            subset_size += path.len();
+            glyphs.push(glyph, path.clone());
+        } else {
+            glyphs.push(glyph, String::new());
+        }
+        paths.push((name, cp, path));
+        used.push(cp);
+    }
+    if let Some(subset) = subset.as_ref() {
+        let size: usize = paths.iter().map(|(_, _, path)| path.len()).sum();
+        println!(
+            "Subset has {} of {} glyphs ({} of {} bytes of paths)",
+            subset.len(),
+            paths.len(),
+            subset_size,
+            size
+        );
+    }
+    font.report(&used);
+
+    // Build table of glyphs that aren't in `Glyph`.
+    let mut extras = Extras::default();
+    if ligatures {
+        need_glyphnames(names_loaded, "--ligatures");
+        extras.add_ligatures(&font, &names);
+    }
+    if alternates {
+        extras.add_alternates(&font, &names, None);
+    } else if !set_types.is_empty() {
+        extras.add_alternates(&font, &names, Some(&set_types));
+    }
+    if !optional_classes.is_empty() {
+        extras.add_optional(&font, &optional_classes);
+    }
+    if !classes.is_empty() {
+        need_glyphnames(names_loaded, "--class");
+        let smufl_classes = smufl_classes.unwrap_or_else(|| {
+            eprintln!("--class needs SMuFL's classes.json from --classes");
+            std::process::exit(1);
+        });
+        extras.add_classes(&font, &names, &smufl_classes, &classes, &used);
+    }
+    if !ranges.is_empty() {
+        need_glyphnames(names_loaded, "--range");
+        let smufl_ranges = smufl_ranges.unwrap_or_else(|| {
+            eprintln!("--range needs SMuFL's ranges.json from --ranges");
+            std::process::exit(1);
+        });
+        smufl_ranges.report(&font, &names, &ranges);
+        extras.add_ranges(&font, &names, &smufl_ranges, &ranges, &used);
+    }
+    if profile {
+        extras.add_profile(&font, &used);
+    }
+    extras.add_composites(&font, &built, &used);
+    if unicode {
+        extras.add_unicode(&used);
+    }
 
     let glyph_paths = glyphs.into_string();
-    let metadata: SMuFLMetadata = json::from_str(&metadata).unwrap();
-    let engraving = metadata.engravingDefaults.unwrap();
+    let metadata = font.metadata;
+    let mut engraving = metadata.engravingDefaults.unwrap();
+    if let Some(scale) = small {
+        optical::adjust_engraving(&mut engraving, scale);
+    }
+    if let Some(amount) = weight {
+        weight::adjust_engraving(&mut engraving, amount);
+    }
     let anchors = metadata.glyphsWithAnchors;
     let bboxes = metadata.glyphBBoxes;
     let convert = |ss: f32| (ss * STAVE_SPACE as f32) as i32;
@@ -704,7 +974,7 @@ fn main() {
     };
     let metadata = SfFontMetadata {
         sffonts_version: 0,
-        font_name: "Modern".to_string(),
+        font_name: if small.is_some() { "Modern Small" } else { "Modern" }.to_string(),
         stave_line_thickness: convert(engraving.staffLineThickness),
         stem_thickness: convert(engraving.stemThickness),
         ledger_line_thickness: convert(engraving.legerLineThickness),
@@ -742,14 +1012,54 @@ fn main() {
         notehead_double_slash: stems("noteheadSlashWhiteDoubleWhole"),
     };
 
-    use std::io::Write;
-
-    let file = std::fs::File::create(out).unwrap();
-    let mut buf_writer = std::io::BufWriter::new(file);
-
-    metadata.write(&mut buf_writer, &glyph_paths).unwrap();
+    let mut data = Vec::new();
+    metadata.write(&mut data, &glyph_paths).unwrap();
+    // A subset only has the glyphs asked for, so leaves out the extra glyphs.
+    if !extras.is_empty() && subset.is_none() {
+        extras.write(&out);
+    }
+    if compress {
+        let compressed = compress::font(&metadata, &glyph_paths);
+        compress::report(&data, &compressed);
+        data = compressed;
+    }
+    std::fs::write(out, data).unwrap();
 
-    buf_writer.flush().unwrap();
+    if let Some(dir) = vfont_dir {
+        vfont::write(&dir, &metadata, &paths);
+    }
+    if let Some(dir) = esm_dir {
+        esm::write(&dir, &metadata, &paths, &anchors, &names);
+    }
+    if let Some(dir) = canvas_dir {
+        canvas::write(&dir, &metadata.font_name, &paths, &font.glyphs);
+    }
+    if let Some(file) = json_file {
+        font_json::write(&file, &metadata, &paths, &anchors, &bboxes, &names, &extras);
+    }
+    if let Some(file) = sprites_file {
+        sprites::write(&file, &metadata.font_name, &paths, &font.glyphs);
+    }
+    if let Some(file) = css_file {
+        css::write(&file, &metadata.font_name, &paths, &font.glyphs);
+    }
+    if let Some(file) = pdf_file {
+        pdf::write(&file, &metadata.font_name, &paths, &font.glyphs);
+    }
+    if let Some(file) = otf_file {
+        otf::write(
+            &file,
+            &metadata.font_name,
+            &font.glyphs,
+            &font.ligatures,
+            &used,
+            &extras,
+            &engraving,
+            &anchors,
+            &bboxes,
+            &names,
+        );
+    }
 
     println!("Done!");
 }
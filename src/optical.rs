@@ -0,0 +1,191 @@
+//! Optical size variants, for a font to use on small (cue and grace) staves.
+
+use crate::glyphnames::{parse_codepoint, GlyphNames};
+use crate::smufl_serde::{EngravingDefaults, GlyphBBox, GlyphWithAnchors};
+use crate::source::{self, Layered};
+
+/// Replace every glyph that has an `opticalVariantsSmall` set member with
+/// that member.  The bounding box is measured from the member's outline, and
+/// its anchors are the member's own if the metadata has them, or else the
+/// glyph's anchors moved to the same place relative to the new bounding box.
+/// Returns the number of glyphs replaced.
+pub fn use_small_variants(font: &mut Layered, names: &GlyphNames) -> usize {
+    let mut variants = Vec::new();
+    for set in font.metadata.sets.values() {
+        if set.r#type != "opticalVariantsSmall" {
+            continue;
+        }
+        for glyph in set.glyphs.iter() {
+            variants.push((
+                glyph.name.clone(),
+                glyph.alternateFor.clone(),
+                parse_codepoint(&glyph.codepoint),
+            ));
+        }
+    }
+
+    let mut replaced = 0;
+    for (name, base_name, codepoint) in variants {
+        let codepoint = match codepoint {
+            Some(cp) => cp,
+            None => continue,
+        };
+        let base = font
+            .alternates
+            .get(&codepoint)
+            .cloned()
+            .or_else(|| names.codepoint(&base_name));
+        let (base, path) = match (base, font.glyphs.get(&codepoint)) {
+            (Some(base), Some(path)) => (base, path.clone()),
+            _ => continue,
+        };
+
+        let meta = &mut font.metadata;
+        let old = meta.glyphBBoxes.remove(&base_name);
+        let new = source::measure(&path);
+        font.glyphs.insert(base, path);
+        if let Some(anchors) = meta.glyphsWithAnchors.remove(&name) {
+            meta.glyphsWithAnchors.insert(base_name.clone(), anchors);
+        } else if let (Some(anchors), Some(old), Some(new)) =
+            (meta.glyphsWithAnchors.get_mut(&base_name), old, new.as_ref())
+        {
+            for point in anchor_points(anchors).iter_mut().filter_map(|p| p.as_mut()) {
+                *point = refit(*point, &old, new);
+            }
+        }
+        meta.glyphBBoxes.remove(&name);
+        if let Some(new) = new {
+            meta.glyphBBoxes.insert(base_name, new);
+        }
+        replaced += 1;
+    }
+    replaced
+}
+
+/// Move a point to the same place relative to bounding box `new` as it is to
+/// `old`.
+pub fn refit(point: [f32; 2], old: &GlyphBBox, new: &GlyphBBox) -> [f32; 2] {
+    let axis = |i: usize| {
+        let (o0, o1, n0, n1) = (old.bBoxSW[i], old.bBoxNE[i], new.bBoxSW[i], new.bBoxNE[i]);
+        if o1 > o0 {
+            n0 + (point[i] - o0) * (n1 - n0) / (o1 - o0)
+        } else {
+            n0 + point[i] - o0
+        }
+    };
+    [axis(0), axis(1)]
+}
+
+/// Adjust line thicknesses for a font drawn at `scale` times the regular
+/// stave size.  Optical variants are drawn heavier than a plain scaled-down
+/// glyph, so lines are thickened to match: half way (geometrically) between
+/// scaling with the stave and keeping their regular size.  Lengths and
+/// distances are left to scale with the stave.
+pub fn adjust_engraving(engraving: &mut EngravingDefaults, scale: f32) {
+    let factor = 1.0 / scale.sqrt();
+
+    for thickness in thicknesses(engraving).iter_mut() {
+        **thickness *= factor;
+    }
+}
+
+/// Every line thickness of the engraving defaults.
+pub fn thicknesses(engraving: &mut EngravingDefaults) -> [&mut f32; 21] {
+    [
+        &mut engraving.staffLineThickness,
+        &mut engraving.stemThickness,
+        &mut engraving.beamThickness,
+        &mut engraving.legerLineThickness,
+        &mut engraving.slurEndpointThickness,
+        &mut engraving.slurMidpointThickness,
+        &mut engraving.tieEndpointThickness,
+        &mut engraving.tieMidpointThickness,
+        &mut engraving.thinBarlineThickness,
+        &mut engraving.thickBarlineThickness,
+        &mut engraving.dashedBarlineThickness,
+        &mut engraving.bracketThickness,
+        &mut engraving.subBracketThickness,
+        &mut engraving.hairpinThickness,
+        &mut engraving.octaveLineThickness,
+        &mut engraving.pedalLineThickness,
+        &mut engraving.repeatEndingLineThickness,
+        &mut engraving.arrowShaftThickness,
+        &mut engraving.lyricLineThickness,
+        &mut engraving.textEnclosureThickness,
+        &mut engraving.tupletBracketThickness,
+    ]
+}
+
+/// Every anchor point of a glyph.
+pub fn anchor_points(anchors: &mut GlyphWithAnchors) -> [&mut Option<[f32; 2]>; 22] {
+    [
+        &mut anchors.splitStemUpSE,
+        &mut anchors.splitStemUpSW,
+        &mut anchors.splitStemDownNE,
+        &mut anchors.splitStemDownNW,
+        &mut anchors.stemUpSE,
+        &mut anchors.stemDownNW,
+        &mut anchors.stemUpNW,
+        &mut anchors.stemDownSW,
+        &mut anchors.nominalWidth,
+        &mut anchors.numeralTop,
+        &mut anchors.numeralBottom,
+        &mut anchors.cutOutNE,
+        &mut anchors.cutOutSE,
+        &mut anchors.cutOutSW,
+        &mut anchors.cutOutNW,
+        &mut anchors.graceNoteSlashSW,
+        &mut anchors.graceNoteSlashNE,
+        &mut anchors.graceNoteSlashNW,
+        &mut anchors.graceNoteSlashSE,
+        &mut anchors.repeatOffset,
+        &mut anchors.noteheadOrigin,
+        &mut anchors.opticalCenter,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{metadata_json, source};
+
+    #[test]
+    fn small_variants_measured_and_anchored() {
+        let meta = metadata_json(
+            r#""glyphBBoxes": {
+                "noteheadBlack": {"bBoxSW": [0, -0.5], "bBoxNE": [1.18, 0.5]},
+                "noteheadHalf": {"bBoxSW": [0, -0.5], "bBoxNE": [1.18, 0.5]}
+            },
+            "glyphsWithAnchors": {
+                "noteheadBlack": {"stemUpSE": [1.18, 0.168]},
+                "noteheadHalf": {"stemUpSE": [1.18, 0.168]},
+                "noteheadHalfSmall": {"stemUpSE": [0.9, 0.1]}
+            },
+            "sets": {"ss01": {"description": "", "type": "opticalVariantsSmall", "glyphs": [
+                {"codepoint": "U+F400", "name": "noteheadBlackSmall",
+                 "alternateFor": "noteheadBlack", "description": ""},
+                {"codepoint": "U+F401", "name": "noteheadHalfSmall",
+                 "alternateFor": "noteheadHalf", "description": ""}
+            ]}}"#,
+        );
+        let big = "M0 125L295 125L295 -125Z";
+        let small = "M0 100L200 100L200 -100Z";
+        let glyphs = [
+            ("uniE0A4", "", big),
+            ("uniE0A3", "", big),
+            ("uniE0A4.ss01", "\u{F400}", small),
+            ("uniE0A3.ss01", "\u{F401}", small),
+        ];
+        let mut font = Layered::new(source("base.svg", &glyphs, meta));
+        assert_eq!(use_small_variants(&mut font, &GlyphNames::default()), 2);
+
+        let meta = &font.metadata;
+        let bbox = &meta.glyphBBoxes["noteheadBlack"];
+        assert_eq!((bbox.bBoxSW, bbox.bBoxNE), ([0.0, -0.4], [0.8, 0.4]));
+        assert!(!meta.glyphBBoxes.contains_key("noteheadBlackSmall"));
+        let [x, y] = meta.glyphsWithAnchors["noteheadBlack"].stemUpSE.unwrap();
+        assert!((x - 0.8).abs() < 1e-5 && (y - 0.1344).abs() < 1e-5);
+        let anchors = &meta.glyphsWithAnchors["noteheadHalf"];
+        assert_eq!(anchors.stemUpSE, Some([0.9, 0.1]));
+    }
+}
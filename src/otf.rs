@@ -0,0 +1,749 @@
+//! OpenType font output, for desktop export to embed the same glyphs as the
+//! `.sfff` font in PDFs and word processors.
+//!
+//! The font has CFF outlines (which keep the cubic curves of the source as
+//! they are), a cmap for the SMuFL codepoints, and the minimum of other
+//! tables that font loaders need.  Next to it goes a SMuFL metadata file for
+//! the same glyphs, for applications to position them.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+
+use serde_json as json;
+use sfff::STAVE_SPACE;
+use svgdom::Path;
+
+use crate::extra::Extras;
+use crate::glyphnames::GlyphNames;
+use crate::outline;
+use crate::smufl_serde::{EngravingDefaults, GlyphBBox, GlyphWithAnchors};
+
+/// Units per em.  SMuFL fonts are one em to four stave spaces.
+const UNITS_PER_EM: i32 = 1000;
+
+/// First string ID of strings that aren't CFF standard strings.
+const CUSTOM_SID: usize = 391;
+
+/// A glyph of the font.
+struct Glyph<'a> {
+    codepoint: u16,
+    name: String,
+    path: &'a Path,
+    /// `[x_min, y_min, x_max, y_max]`, in font units.
+    bounds: [i32; 4],
+    advance: i32,
+}
+
+/// Write an OpenType font with the glyphs at `used` and at the codepoints of
+/// `extras` (ligatures taking their outline from `ligatures`), and its SMuFL
+/// metadata next to it (with the extension changed to
+/// `.metadata.json`, so it doesn't overwrite a `--json` font).
+#[allow(clippy::too_many_arguments)]
+pub fn write(
+    file: &str,
+    font_name: &str,
+    font: &HashMap<u16, Path>,
+    ligatures: &HashMap<Vec<u16>, Path>,
+    used: &[u16],
+    extras: &Extras,
+    engraving: &EngravingDefaults,
+    anchors: &HashMap<String, GlyphWithAnchors>,
+    bboxes: &HashMap<String, GlyphBBox>,
+    names: &GlyphNames,
+) {
+    let family = format!("ScoreFall {}", font_name);
+    let glyphs = glyphs(font, ligatures, &codepoints(used, extras), extras, names);
+
+    let metadata = metadata(&family, &glyphs, engraving, anchors, bboxes);
+    let sidecar = std::path::Path::new(file).with_extension("metadata.json");
+    write_file(&sidecar.to_string_lossy(), json::to_string_pretty(&metadata).unwrap().as_bytes());
+    write_file(file, &sfnt(&family, &glyphs));
+    println!("Wrote {} glyphs to {}", glyphs.len(), file);
+}
+
+fn write_file(file: &str, data: &[u8]) {
+    std::fs::write(file, data).unwrap_or_else(|e| {
+        eprintln!("Failed to write {}: {}", file, e);
+        std::process::exit(1);
+    });
+}
+
+/// Codepoints of every glyph written to the `.sfff` font, in order.
+fn codepoints(used: &[u16], extras: &Extras) -> Vec<u16> {
+    let mut codepoints: Vec<u16> = used.to_vec();
+    codepoints.extend(extras.ligatures.iter().filter_map(|ligature| ligature.codepoint));
+    codepoints.extend(extras.alternates.iter().map(|alternate| alternate.codepoint));
+    codepoints.extend(extras.optional.iter().map(|optional| optional.codepoint));
+    codepoints.extend(extras.composites.iter().map(|composite| composite.codepoint));
+    codepoints.extend(extras.recommended.iter().map(|glyph| glyph.id));
+    codepoints.sort_unstable();
+    codepoints.dedup();
+    codepoints
+}
+
+/// Glyphs at `codepoints` that have an outline, named by their SMuFL name
+/// (or `uniXXXX`, if it isn't known).
+fn glyphs<'a>(
+    font: &'a HashMap<u16, Path>,
+    ligatures: &'a HashMap<Vec<u16>, Path>,
+    codepoints: &[u16],
+    extras: &Extras,
+    names: &GlyphNames,
+) -> Vec<Glyph<'a>> {
+    let mut seen = HashSet::new();
+    let mut glyphs = Vec::new();
+    for &codepoint in codepoints {
+        let ligature = || {
+            let ligature = extras.ligatures.iter().find(|l| l.codepoint == Some(codepoint))?;
+            ligatures.get(&ligature.components)
+        };
+        let path = match font.get(&codepoint).or_else(ligature) {
+            Some(path) => path,
+            None => continue,
+        };
+        let name = match names.name(codepoint).or_else(|| extra_name(extras, codepoint)) {
+            Some(name) if seen.insert(name.to_string()) => name.to_string(),
+            _ => format!("uni{:04X}", codepoint),
+        };
+        let bounds = match outline::bounds(path) {
+            Some([x0, y0, x1, y1]) => {
+                [units(x0).floor(), units(y0).floor(), units(x1).ceil(), units(y1).ceil()]
+            }
+            None => [0.0; 4],
+        }
+        .map(|v| v as i32);
+        let advance = bounds[2].max(0);
+        glyphs.push(Glyph { codepoint, name, path, bounds, advance });
+    }
+    glyphs
+}
+
+/// Name the extra glyph table gives the glyph at `codepoint`.
+fn extra_name(extras: &Extras, codepoint: u16) -> Option<&str> {
+    let ligatures = extras.ligatures.iter();
+    let ligatures = ligatures.filter_map(|l| Some((l.codepoint?, l.name.as_deref()?)));
+    let alternates = extras.alternates.iter().map(|a| (a.codepoint, a.name.as_str()));
+    let optional = extras.optional.iter().chain(extras.composites.iter());
+    let optional = optional.map(|o| (o.codepoint, o.name.as_str()));
+    let recommended = extras.recommended.iter().map(|r| (r.id, r.name.as_str()));
+    let mut named = ligatures.chain(alternates).chain(optional).chain(recommended);
+    named.find(|(cp, _)| *cp == codepoint).map(|(_, name)| name)
+}
+
+/// ScoreFall units to font units.
+fn units(v: f64) -> f64 {
+    v * f64::from(UNITS_PER_EM) / (4.0 * STAVE_SPACE as f64)
+}
+
+/// SMuFL metadata of the glyphs.  Anchors and bounding boxes are in stave
+/// spaces, as in the source metadata.
+fn metadata(
+    family: &str,
+    glyphs: &[Glyph],
+    engraving: &EngravingDefaults,
+    anchors: &HashMap<String, GlyphWithAnchors>,
+    bboxes: &HashMap<String, GlyphBBox>,
+) -> json::Value {
+    let mut glyph_bboxes = BTreeMap::new();
+    let mut glyphs_with_anchors = BTreeMap::new();
+    for glyph in glyphs {
+        if let Some(bbox) = bboxes.get(&glyph.name) {
+            glyph_bboxes.insert(glyph.name.clone(), json::to_value(bbox).unwrap());
+        }
+        if let Some(json::Value::Object(mut points)) =
+            anchors.get(&glyph.name).map(|a| json::to_value(a).unwrap())
+        {
+            points.retain(|_, point| !point.is_null());
+            glyphs_with_anchors.insert(glyph.name.clone(), json::Value::Object(points));
+        }
+    }
+    json::json!({
+        "fontName": family,
+        "fontVersion": 1.0,
+        "engravingDefaults": engraving,
+        "glyphBBoxes": glyph_bboxes,
+        "glyphsWithAnchors": glyphs_with_anchors,
+    })
+}
+
+/// The whole OpenType file.
+fn sfnt(family: &str, glyphs: &[Glyph]) -> Vec<u8> {
+    let postscript_name = family.replace(' ', "");
+    let bounds = glyphs.iter().map(|g| g.bounds).filter(|b| b != &[0; 4]).fold(
+        [i32::MAX, i32::MAX, i32::MIN, i32::MIN],
+        |a, b| [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])],
+    );
+    let bounds = if bounds[0] > bounds[2] { [0; 4] } else { bounds };
+
+    // Tables, sorted by tag.
+    let tables: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"CFF ", cff(&postscript_name, family, glyphs, bounds)),
+        (b"OS/2", os2(glyphs, bounds)),
+        (b"cmap", cmap(glyphs)),
+        (b"head", head(bounds)),
+        (b"hhea", hhea(glyphs, bounds)),
+        (b"hmtx", hmtx(glyphs)),
+        (b"maxp", maxp(glyphs)),
+        (b"name", name(family, &postscript_name)),
+        (b"post", post()),
+    ];
+
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = 16 << entry_selector;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"OTTO");
+    push16(&mut out, count);
+    push16(&mut out, search_range);
+    push16(&mut out, entry_selector);
+    push16(&mut out, count * 16 - search_range);
+
+    let mut offset = 12 + 16 * tables.len();
+    let mut data = Vec::new();
+    let mut head_offset = 0;
+    for (tag, table) in tables.iter() {
+        if tag == &b"head" {
+            head_offset = offset;
+        }
+        out.extend_from_slice(&tag[..]);
+        push32(&mut out, checksum(table));
+        push32(&mut out, offset as u32);
+        push32(&mut out, table.len() as u32);
+        data.extend_from_slice(table);
+        data.resize((data.len() + 3) & !3, 0);
+        offset = 12 + 16 * tables.len() + data.len();
+    }
+    out.extend_from_slice(&data);
+
+    let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(checksum(&out));
+    out[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    out
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn push16(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+fn push32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+fn head(bounds: [i32; 4]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push32(&mut out, 0x0001_0000); // version
+    push32(&mut out, 0x0001_0000); // fontRevision
+    push32(&mut out, 0); // checkSumAdjustment, set once the file is complete
+    push32(&mut out, 0x5F0F_3CF5); // magicNumber
+    push16(&mut out, 0b11); // flags: baseline and left side bearing at 0
+    push16(&mut out, UNITS_PER_EM as u16);
+    out.extend_from_slice(&[0; 16]); // created, modified
+    for v in bounds.iter() {
+        push16(&mut out, *v as u16);
+    }
+    push16(&mut out, 0); // macStyle
+    push16(&mut out, 8); // lowestRecPPEM
+    push16(&mut out, 2); // fontDirectionHint
+    push16(&mut out, 0); // indexToLocFormat
+    push16(&mut out, 0); // glyphDataFormat
+    out
+}
+
+fn hhea(glyphs: &[Glyph], bounds: [i32; 4]) -> Vec<u8> {
+    let inked = || glyphs.iter().filter(|g| g.bounds != [0; 4]);
+    let mut out = Vec::new();
+    push32(&mut out, 0x0001_0000);
+    push16(&mut out, bounds[3] as u16); // ascender
+    push16(&mut out, bounds[1] as u16); // descender
+    push16(&mut out, 0); // lineGap
+    push16(&mut out, glyphs.iter().map(|g| g.advance).max().unwrap_or(0) as u16);
+    push16(&mut out, inked().map(|g| g.bounds[0]).min().unwrap_or(0) as u16);
+    push16(&mut out, inked().map(|g| g.advance - g.bounds[2]).min().unwrap_or(0) as u16);
+    push16(&mut out, inked().map(|g| g.bounds[2]).max().unwrap_or(0) as u16);
+    push16(&mut out, 1); // caretSlopeRise
+    push16(&mut out, 0); // caretSlopeRun
+    out.extend_from_slice(&[0; 10]); // caretOffset, reserved
+    push16(&mut out, 0); // metricDataFormat
+    push16(&mut out, glyphs.len() as u16 + 1); // numberOfHMetrics
+    out
+}
+
+fn hmtx(glyphs: &[Glyph]) -> Vec<u8> {
+    let mut out = vec![0; 4]; // .notdef
+    for glyph in glyphs {
+        push16(&mut out, glyph.advance as u16);
+        push16(&mut out, glyph.bounds[0] as u16);
+    }
+    out
+}
+
+fn maxp(glyphs: &[Glyph]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push32(&mut out, 0x0000_5000);
+    push16(&mut out, glyphs.len() as u16 + 1);
+    out
+}
+
+fn os2(glyphs: &[Glyph], bounds: [i32; 4]) -> Vec<u8> {
+    let advances: Vec<i32> = glyphs.iter().map(|g| g.advance).filter(|a| *a > 0).collect();
+    let average = advances.iter().sum::<i32>() / (advances.len() as i32).max(1);
+    let first = glyphs.iter().map(|g| g.codepoint).min().unwrap_or(0);
+    let last = glyphs.iter().map(|g| g.codepoint).max().unwrap_or(0);
+
+    let mut out = Vec::new();
+    push16(&mut out, 4); // version
+    push16(&mut out, average as u16); // xAvgCharWidth
+    push16(&mut out, 400); // usWeightClass
+    push16(&mut out, 5); // usWidthClass
+    push16(&mut out, 0); // fsType: installable embedding
+    for v in [650, 600, 0, 75, 650, 600, 0, 350, 50, 250].iter() {
+        push16(&mut out, *v); // subscript, superscript and strikeout
+    }
+    push16(&mut out, 0); // sFamilyClass
+    out.extend_from_slice(&[0; 10]); // panose
+    push32(&mut out, 0); // ulUnicodeRange1
+    push32(&mut out, 1 << 28); // ulUnicodeRange2: Private Use Area
+    push32(&mut out, 0); // ulUnicodeRange3
+    push32(&mut out, 0); // ulUnicodeRange4
+    out.extend_from_slice(b"NONE"); // achVendID
+    push16(&mut out, 0x40); // fsSelection: regular
+    push16(&mut out, first);
+    push16(&mut out, last);
+    push16(&mut out, bounds[3] as u16); // sTypoAscender
+    push16(&mut out, bounds[1] as u16); // sTypoDescender
+    push16(&mut out, 0); // sTypoLineGap
+    push16(&mut out, bounds[3].max(0) as u16); // usWinAscent
+    push16(&mut out, (-bounds[1]).max(0) as u16); // usWinDescent
+    push32(&mut out, 0); // ulCodePageRange1
+    push32(&mut out, 0); // ulCodePageRange2
+    push16(&mut out, 0); // sxHeight
+    push16(&mut out, 0); // sCapHeight
+    push16(&mut out, 0); // usDefaultChar
+    push16(&mut out, 0x20); // usBreakChar
+    push16(&mut out, 0); // usMaxContext
+    out
+}
+
+/// Unicode cmap, in format 4 (all SMuFL codepoints are in the BMP).  Glyph
+/// IDs follow the codepoints, so each run of consecutive codepoints is one
+/// segment.
+fn cmap(glyphs: &[Glyph]) -> Vec<u8> {
+    // (start, end, glyph ID of start).
+    let mut segments: Vec<(u16, u16, u16)> = Vec::new();
+    for (i, glyph) in glyphs.iter().enumerate() {
+        match segments.last_mut() {
+            Some(segment) if u32::from(segment.1) + 1 == u32::from(glyph.codepoint) => {
+                segment.1 = glyph.codepoint;
+            }
+            _ => segments.push((glyph.codepoint, glyph.codepoint, i as u16 + 1)),
+        }
+    }
+    if segments.last().is_none_or(|s| s.1 != 0xFFFF) {
+        segments.push((0xFFFF, 0xFFFF, 0));
+    }
+
+    let count = segments.len() as u16;
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = 2 << entry_selector;
+    let mut subtable = Vec::new();
+    push16(&mut subtable, 4); // format
+    push16(&mut subtable, 16 + 8 * count); // length
+    push16(&mut subtable, 0); // language
+    push16(&mut subtable, count * 2);
+    push16(&mut subtable, search_range);
+    push16(&mut subtable, entry_selector);
+    push16(&mut subtable, count * 2 - search_range);
+    for segment in segments.iter() {
+        push16(&mut subtable, segment.1);
+    }
+    push16(&mut subtable, 0); // reservedPad
+    for segment in segments.iter() {
+        push16(&mut subtable, segment.0);
+    }
+    for segment in segments.iter() {
+        let delta = if segment.2 == 0 { 1 } else { segment.2.wrapping_sub(segment.0) };
+        push16(&mut subtable, delta);
+    }
+    for _ in segments.iter() {
+        push16(&mut subtable, 0); // idRangeOffset
+    }
+
+    let mut out = Vec::new();
+    push16(&mut out, 0); // version
+    push16(&mut out, 2); // numTables
+    for (platform, encoding) in [(0, 3), (3, 1)].iter() {
+        push16(&mut out, *platform);
+        push16(&mut out, *encoding);
+        push32(&mut out, 20);
+    }
+    out.extend_from_slice(&subtable);
+    out
+}
+
+fn name(family: &str, postscript_name: &str) -> Vec<u8> {
+    let records = [
+        (1, family.to_string()),
+        (2, "Regular".to_string()),
+        (3, format!("1.000;{}", postscript_name)),
+        (4, family.to_string()),
+        (5, "Version 1.000".to_string()),
+        (6, postscript_name.to_string()),
+    ];
+
+    let mut out = Vec::new();
+    let mut strings = Vec::new();
+    push16(&mut out, 0); // format
+    push16(&mut out, records.len() as u16);
+    push16(&mut out, 6 + 12 * records.len() as u16);
+    for (id, string) in records.iter() {
+        let utf16: Vec<u8> = string.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        push16(&mut out, 3); // Windows
+        push16(&mut out, 1); // Unicode BMP
+        push16(&mut out, 0x409); // English (United States)
+        push16(&mut out, *id);
+        push16(&mut out, utf16.len() as u16);
+        push16(&mut out, strings.len() as u16);
+        strings.extend_from_slice(&utf16);
+    }
+    out.extend_from_slice(&strings);
+    out
+}
+
+fn post() -> Vec<u8> {
+    let mut out = Vec::new();
+    push32(&mut out, 0x0003_0000); // version 3: glyph names are in CFF
+    push32(&mut out, 0); // italicAngle
+    push16(&mut out, (-100i16) as u16); // underlinePosition
+    push16(&mut out, 50); // underlineThickness
+    out.extend_from_slice(&[0; 20]); // isFixedPitch, memory usage
+    out
+}
+
+/// The CFF table: one font with a charstring for each glyph after
+/// `.notdef`, no subroutines, and an empty private dictionary.
+fn cff(postscript_name: &str, family: &str, glyphs: &[Glyph], bounds: [i32; 4]) -> Vec<u8> {
+    let mut strings: Vec<&str> = vec!["1.000", family];
+    strings.extend(glyphs.iter().map(|g| g.name.as_str()));
+    let sid = |i: usize| (CUSTOM_SID + i) as i32;
+
+    let mut charstrings = vec![charstring(None, 0)];
+    charstrings.extend(glyphs.iter().map(|g| charstring(Some(g.path), g.advance)));
+    let charstrings = index(&charstrings);
+
+    let mut charset = vec![0]; // format
+    for i in 0..glyphs.len() {
+        push16(&mut charset, sid(2 + i) as u16);
+    }
+
+    let mut private = Vec::new();
+    dict_number(&mut private, 0);
+    private.push(20); // defaultWidthX
+    dict_number(&mut private, 0);
+    private.push(21); // nominalWidthX
+
+    // The offsets in the top dictionary are always five bytes long, so its
+    // size doesn't depend on them.
+    let top = |charset_offset: usize, charstrings_offset: usize, private_offset: usize| {
+        let mut top = Vec::new();
+        dict_number(&mut top, sid(0));
+        top.push(0); // version
+        dict_number(&mut top, sid(1));
+        top.push(2); // FullName
+        dict_number(&mut top, sid(1));
+        top.push(3); // FamilyName
+        for v in bounds.iter() {
+            dict_number(&mut top, *v);
+        }
+        top.push(5); // FontBBox
+        dict_offset(&mut top, charset_offset);
+        top.push(15); // charset
+        dict_offset(&mut top, charstrings_offset);
+        top.push(17); // CharStrings
+        dict_number(&mut top, private.len() as i32);
+        dict_offset(&mut top, private_offset);
+        top.push(18); // Private
+        top
+    };
+
+    let header = [1, 0, 4, 4];
+    let names = index(&[postscript_name.as_bytes().to_vec()]);
+    let strings = index(&strings.iter().map(|s| s.as_bytes().to_vec()).collect::<Vec<_>>());
+    let global_subrs = index(&[]);
+    let top_size = index(&[top(0, 0, 0)]).len();
+    let charset_offset = header.len() + names.len() + top_size + strings.len() + global_subrs.len();
+    let charstrings_offset = charset_offset + charset.len();
+    let private_offset = charstrings_offset + charstrings.len();
+
+    let mut out = header.to_vec();
+    out.extend_from_slice(&names);
+    out.extend_from_slice(&index(&[top(charset_offset, charstrings_offset, private_offset)]));
+    out.extend_from_slice(&strings);
+    out.extend_from_slice(&global_subrs);
+    out.extend_from_slice(&charset);
+    out.extend_from_slice(&charstrings);
+    out.extend_from_slice(&private);
+    out
+}
+
+/// A CFF INDEX of `items`.
+fn index(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push16(&mut out, items.len() as u16);
+    if items.is_empty() {
+        return out;
+    }
+    let end = items.iter().map(Vec::len).sum::<usize>() + 1;
+    let size = match end {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x1_0000..=0xFF_FFFF => 3,
+        _ => 4,
+    };
+    out.push(size as u8);
+    let mut offset = 1;
+    for item in std::iter::once(&Vec::new()).chain(items.iter()) {
+        offset += item.len();
+        out.extend_from_slice(&(offset as u32).to_be_bytes()[4 - size..]);
+    }
+    for item in items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+/// An integer operand, in the encoding shared by dictionaries and
+/// charstrings for small values.
+fn small_number(out: &mut Vec<u8>, v: i32) -> bool {
+    match v {
+        -107..=107 => out.push((v + 139) as u8),
+        108..=1131 => out.extend_from_slice(&[((v - 108) >> 8) as u8 + 247, (v - 108) as u8]),
+        -1131..=-108 => out.extend_from_slice(&[((-v - 108) >> 8) as u8 + 251, (-v - 108) as u8]),
+        _ => return false,
+    }
+    true
+}
+
+fn dict_number(out: &mut Vec<u8>, v: i32) {
+    if small_number(out, v) {
+        return;
+    }
+    match i16::try_from(v) {
+        Ok(v) => {
+            out.push(28);
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+        Err(_) => dict_offset(out, v as usize),
+    }
+}
+
+/// A dictionary operand in the five byte encoding.
+fn dict_offset(out: &mut Vec<u8>, v: usize) {
+    out.push(29);
+    out.extend_from_slice(&(v as i32).to_be_bytes());
+}
+
+fn charstring_number(out: &mut Vec<u8>, v: i32) {
+    if !small_number(out, v) {
+        let v = v.clamp(i16::MIN.into(), i16::MAX.into()) as i16;
+        out.push(28);
+        out.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+/// Type 2 charstring of an outline (with the y axis up, in ScoreFall units)
+/// and its advance width.  Points are rounded to whole font units, and each
+/// is given relative to the previous rounded point so rounding errors don't
+/// add up.
+fn charstring(path: Option<&Path>, advance: i32) -> Vec<u8> {
+    use svgdom::PathSegment::*;
+
+    const RMOVETO: u8 = 21;
+    const RLINETO: u8 = 5;
+    const RRCURVETO: u8 = 8;
+    const ENDCHAR: u8 = 14;
+
+    let mut out = Vec::new();
+    charstring_number(&mut out, advance);
+
+    let segments = match path {
+        Some(path) => outline::cubic(path),
+        None => Path::new(),
+    };
+    let mut pen = (0, 0);
+    let mut emit = |out: &mut Vec<u8>, points: &[(f64, f64)], operator: u8| {
+        for point in points {
+            let point = (units(point.0).round() as i32, units(point.1).round() as i32);
+            charstring_number(out, point.0 - pen.0);
+            charstring_number(out, point.1 - pen.1);
+            pen = point;
+        }
+        out.push(operator);
+    };
+    for segment in segments.iter() {
+        match *segment {
+            MoveTo { x, y, .. } => emit(&mut out, &[(x, y)], RMOVETO),
+            LineTo { x, y, .. } => emit(&mut out, &[(x, y)], RLINETO),
+            CurveTo { x1, y1, x2, y2, x, y, .. } => {
+                emit(&mut out, &[(x1, y1), (x2, y2), (x, y)], RRCURVETO);
+            }
+            // Contours close themselves, and the pen stays where it is.
+            _ => {}
+        }
+    }
+    out.push(ENDCHAR);
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra::{Ligature, Optional, Recommended};
+    use crate::source::tests::{repo_file, temp_file};
+
+    fn u16_at(data: &[u8], offset: usize) -> u16 {
+        u16::from_be_bytes([data[offset], data[offset + 1]])
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+    }
+
+    /// Tables of an OpenType file by tag, checking the table directory.
+    fn tables(otf: &[u8]) -> HashMap<String, &[u8]> {
+        assert_eq!(&otf[..4], b"OTTO");
+        let count = usize::from(u16_at(otf, 4));
+        let mut tables = HashMap::new();
+        let mut last_tag = String::new();
+        for record in otf[12..12 + 16 * count].chunks(16) {
+            let tag = String::from_utf8(record[..4].to_vec()).unwrap();
+            assert!(tag > last_tag, "{} isn't sorted", tag);
+            let (offset, length) = (u32_at(record, 8) as usize, u32_at(record, 12) as usize);
+            assert_eq!(offset % 4, 0, "{} isn't aligned", tag);
+            let mut table = otf[offset..offset + length].to_vec();
+            if tag == "head" {
+                table[8..12].copy_from_slice(&[0; 4]);
+            }
+            assert_eq!(checksum(&table), u32_at(record, 4), "{} checksum", tag);
+            tables.insert(tag.clone(), &otf[offset..offset + length]);
+            last_tag = tag;
+        }
+        assert_eq!(checksum(otf), 0xB1B0_AFBA);
+        tables
+    }
+
+    /// Glyph ID of `codepoint` in a format 4 cmap.
+    fn glyph_id(cmap: &[u8], codepoint: u16) -> u16 {
+        let subtable = &cmap[u32_at(cmap, 8) as usize..];
+        assert_eq!(u16_at(subtable, 0), 4);
+        let count = usize::from(u16_at(subtable, 6) / 2);
+        for i in 0..count {
+            let end = u16_at(subtable, 14 + 2 * i);
+            let start = u16_at(subtable, 16 + 2 * count + 2 * i);
+            if (start..=end).contains(&codepoint) {
+                return codepoint.wrapping_add(u16_at(subtable, 16 + 4 * count + 2 * i));
+            }
+        }
+        0
+    }
+
+    #[test]
+    fn tables_and_metadata() {
+        let file = temp_file("font.otf");
+        let sidecar = temp_file("font.metadata.json");
+        let mut font = HashMap::new();
+        font.insert(0xE050, "M0 -100L100 -100L100 300Z".parse().unwrap());
+        font.insert(0xE0A4, "M0 -50L118 -50L118 50L0 50Z".parse().unwrap());
+        font.insert(0xF400, "M0 -40L94 -40L94 40L0 40Z".parse().unwrap());
+        font.insert(0xE4A0, "M0 0L100 50L0 100Z".parse().unwrap());
+        let mut ligatures = HashMap::new();
+        ligatures.insert(vec![0xE062, 0xE888], "M0 0L200 0L200 200Z".parse().unwrap());
+        let mut extras = Extras::default();
+        extras.optional.push(Optional {
+            name: "noteheadBlackSmall".to_string(),
+            codepoint: 0xF400,
+            classes: Vec::new(),
+            path: String::new(),
+        });
+        extras.recommended.push(Recommended {
+            id: 0xE4A0,
+            name: "articAccentAbove".to_string(),
+            classes: vec!["articulations".to_string()],
+            ranges: Vec::new(),
+            path: String::new(),
+        });
+        extras.ligatures.push(Ligature {
+            name: Some("fClef8vbLigature".to_string()),
+            codepoint: Some(0xF4A0),
+            components: vec![0xE062, 0xE888],
+            path: String::new(),
+        });
+        let meta: json::Value =
+            json::from_str(&std::fs::read_to_string(repo_file("font-main/meta.json")).unwrap())
+                .unwrap();
+        let engraving: EngravingDefaults =
+            json::from_value(meta["engravingDefaults"].clone()).unwrap();
+        let mut anchors = HashMap::new();
+        anchors.insert(
+            "noteheadBlack".to_string(),
+            json::from_str(r#"{"stemUpSE": [1.18, 0.168]}"#).unwrap(),
+        );
+        let mut bboxes = HashMap::new();
+        bboxes.insert(
+            "noteheadBlack".to_string(),
+            GlyphBBox { bBoxNE: [1.18, 0.5], bBoxSW: [0.0, -0.5] },
+        );
+        bboxes.insert(
+            "articAccentAbove".to_string(),
+            GlyphBBox { bBoxNE: [1.0, 1.0], bBoxSW: [0.0, 0.0] },
+        );
+        let used = [0xE0A4, 0xE050, 0xE0A0];
+        let names = GlyphNames::bundled();
+        write(
+            &file, "Test", &font, &ligatures, &used, &extras, &engraving, &anchors, &bboxes, &names,
+        );
+        let otf = std::fs::read(&file).unwrap();
+        let metadata: json::Value =
+            json::from_str(&std::fs::read_to_string(&sidecar).unwrap()).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        std::fs::remove_file(&sidecar).unwrap();
+
+        let tables = tables(&otf);
+        let mut tags: Vec<&str> = tables.keys().map(String::as_str).collect();
+        tags.sort_unstable();
+        assert_eq!(tags, ["CFF ", "OS/2", "cmap", "head", "hhea", "hmtx", "maxp", "name", "post"]);
+        // .notdef, then the glyphs with outlines by codepoint.
+        assert_eq!(u16_at(tables["maxp"], 4), 6);
+        assert_eq!(glyph_id(tables["cmap"], 0xE050), 1);
+        assert_eq!(glyph_id(tables["cmap"], 0xE0A4), 2);
+        assert_eq!(glyph_id(tables["cmap"], 0xE4A0), 3);
+        assert_eq!(glyph_id(tables["cmap"], 0xF400), 4);
+        assert_eq!(glyph_id(tables["cmap"], 0xF4A0), 5);
+        assert_eq!(glyph_id(tables["cmap"], 0xE0A0), 0);
+        // The notehead's advance and left side bearing, at 250 units per
+        // stave space.
+        assert_eq!(&tables["hmtx"][8..12], &[0x01, 0x27, 0, 0]);
+        let cff = tables["CFF "];
+        let names =
+            ["ScoreFallTest", "gClef", "noteheadBlackSmall", "articAccentAbove", "fClef8vbLigature"];
+        for name in names.iter() {
+            assert!(cff.windows(name.len()).any(|w| w == name.as_bytes()), "{}", name);
+        }
+        assert!(!cff.windows(3).any(|w| w == b"uni"));
+
+        assert_eq!(metadata["fontName"], "ScoreFall Test");
+        let stem = metadata["engravingDefaults"]["stemThickness"].as_f64().unwrap();
+        assert!((stem - f64::from(engraving.stemThickness)).abs() < 1e-6);
+        let bboxes = metadata["glyphBBoxes"].as_object().unwrap();
+        assert_eq!(bboxes.keys().collect::<Vec<_>>(), ["articAccentAbove", "noteheadBlack"]);
+        let anchors = &metadata["glyphsWithAnchors"]["noteheadBlack"];
+        assert_eq!(anchors.as_object().unwrap().keys().collect::<Vec<_>>(), ["stemUpSE"]);
+    }
+}
@@ -0,0 +1,431 @@
+//! Operations on glyph outlines, in ScoreFall units with the y axis up.
+
+use svgdom::Path;
+
+/// Copy of a path with every segment converted to absolute coordinates.
+pub fn absolute(path: &Path) -> Path {
+    let mut path = path.clone();
+    path.conv_to_absolute();
+    path
+}
+
+/// Scale a path about the origin, then move it by `dx`, `dy`.
+pub fn transform(path: &Path, scale: f64, dx: f64, dy: f64) -> Path {
+    stretch(path, scale, scale, dx, dy)
+}
+
+/// Scale a path about the origin by `sx` horizontally and `sy` vertically,
+/// then move it by `dx`, `dy`.  Arcs are only stretched correctly if they
+/// aren't rotated.
+pub fn stretch(path: &Path, sx: f64, sy: f64, dx: f64, dy: f64) -> Path {
+    use svgdom::PathSegment::*;
+
+    let x = |x: f64| x * sx + dx;
+    let y = |y: f64| y * sy + dy;
+
+    let mut output = Path::with_capacity(path.len());
+    for i in absolute(path).iter() {
+        output.push(match *i {
+            MoveTo { abs, x: x0, y: y0 } => MoveTo { abs, x: x(x0), y: y(y0) },
+            LineTo { abs, x: x0, y: y0 } => LineTo { abs, x: x(x0), y: y(y0) },
+            HorizontalLineTo { abs, x: x0 } => HorizontalLineTo { abs, x: x(x0) },
+            VerticalLineTo { abs, y: y0 } => VerticalLineTo { abs, y: y(y0) },
+            CurveTo { abs, x1, y1, x2, y2, x: x0, y: y0 } => CurveTo {
+                abs,
+                x1: x(x1),
+                y1: y(y1),
+                x2: x(x2),
+                y2: y(y2),
+                x: x(x0),
+                y: y(y0),
+            },
+            SmoothCurveTo { abs, x2, y2, x: x0, y: y0 } => SmoothCurveTo {
+                abs,
+                x2: x(x2),
+                y2: y(y2),
+                x: x(x0),
+                y: y(y0),
+            },
+            Quadratic { abs, x1, y1, x: x0, y: y0 } => Quadratic {
+                abs,
+                x1: x(x1),
+                y1: y(y1),
+                x: x(x0),
+                y: y(y0),
+            },
+            SmoothQuadratic { abs, x: x0, y: y0 } => SmoothQuadratic { abs, x: x(x0), y: y(y0) },
+            EllipticalArc { abs, rx, ry, x_axis_rotation, large_arc, sweep, x: x0, y: y0 } => {
+                EllipticalArc {
+                    abs,
+                    rx: rx * sx,
+                    ry: ry * sy,
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    x: x(x0),
+                    y: y(y0),
+                }
+            }
+            ClosePath { abs } => ClosePath { abs },
+        });
+    }
+    output
+}
+
+/// Append the contours of `other` to `path`.
+pub fn append(path: &mut Path, other: &Path) {
+    let other = absolute(other);
+    path.extend(other.iter().cloned());
+}
+
+/// Bounding box of the points and control points of a path, as
+/// `[min_x, min_y, max_x, max_y]`.  Curves never extend past their control
+/// points, so this contains the whole outline.
+pub fn bounds(path: &Path) -> Option<[f64; 4]> {
+    use svgdom::PathSegment::*;
+
+    let mut bounds: Option<[f64; 4]> = None;
+    let mut add = |x: f64, y: f64| {
+        bounds = Some(match bounds {
+            Some([x0, y0, x1, y1]) => [x0.min(x), y0.min(y), x1.max(x), y1.max(y)],
+            None => [x, y, x, y],
+        });
+    };
+
+    let (mut cx, mut cy) = (0.0, 0.0);
+    let (mut sx, mut sy) = (0.0, 0.0);
+    for i in absolute(path).iter() {
+        match *i {
+            MoveTo { x, y, .. } => {
+                add(x, y);
+                cx = x;
+                cy = y;
+                sx = x;
+                sy = y;
+            }
+            LineTo { x, y, .. } | SmoothQuadratic { x, y, .. } => {
+                add(x, y);
+                cx = x;
+                cy = y;
+            }
+            HorizontalLineTo { x, .. } => {
+                add(x, cy);
+                cx = x;
+            }
+            VerticalLineTo { y, .. } => {
+                add(cx, y);
+                cy = y;
+            }
+            CurveTo { x1, y1, x2, y2, x, y, .. } => {
+                add(x1, y1);
+                add(x2, y2);
+                add(x, y);
+                cx = x;
+                cy = y;
+            }
+            SmoothCurveTo { x2, y2, x, y, .. } => {
+                add(x2, y2);
+                add(x, y);
+                cx = x;
+                cy = y;
+            }
+            Quadratic { x1, y1, x, y, .. } => {
+                add(x1, y1);
+                add(x, y);
+                cx = x;
+                cy = y;
+            }
+            EllipticalArc { rx, ry, x, y, .. } => {
+                // The arc stays within its radii of both endpoints.
+                let r = rx.abs().max(ry.abs());
+                add(cx - r, cy - r);
+                add(cx + r, cy + r);
+                add(x - r, y - r);
+                add(x + r, y + r);
+                cx = x;
+                cy = y;
+            }
+            ClosePath { .. } => {
+                cx = sx;
+                cy = sy;
+            }
+        }
+    }
+    bounds
+}
+
+/// Bounding box of the outline itself, as `[min_x, min_y, max_x, max_y]`.
+/// Unlike `bounds`, curves are measured at their extremes rather than their
+/// control points, so this is the box SMuFL metadata gives.
+pub fn extent(path: &Path) -> Option<[f64; 4]> {
+    use svgdom::PathSegment::*;
+
+    let mut extent: Option<[f64; 4]> = None;
+    let mut add = |x: f64, y: f64| {
+        extent = Some(match extent {
+            Some([x0, y0, x1, y1]) => [x0.min(x), y0.min(y), x1.max(x), y1.max(y)],
+            None => [x, y, x, y],
+        });
+    };
+
+    let mut current = (0.0, 0.0);
+    for segment in cubic(path).iter() {
+        match *segment {
+            MoveTo { x, y, .. } | LineTo { x, y, .. } => {
+                add(x, y);
+                current = (x, y);
+            }
+            CurveTo { x1, y1, x2, y2, x, y, .. } => {
+                let (x0, y0) = current;
+                let at = |a: f64, b: f64, c: f64, d: f64, t: f64| {
+                    let u = 1.0 - t;
+                    u * u * u * a + 3.0 * u * u * t * b + 3.0 * u * t * t * c + t * t * t * d
+                };
+                for t in extremes(x0, x1, x2, x).into_iter().chain(extremes(y0, y1, y2, y)) {
+                    add(at(x0, x1, x2, x, t), at(y0, y1, y2, y, t));
+                }
+                add(x, y);
+                current = (x, y);
+            }
+            _ => {}
+        }
+    }
+    extent
+}
+
+/// Curve parameters strictly between 0 and 1 where one coordinate of a cubic
+/// curve, with coordinates `a`, `b`, `c` and `d` of its points, turns.
+fn extremes(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // Roots of the derivative, a quadratic q t² + r t + s.
+    let (q, r, s) = (3.0 * (-a + 3.0 * b - 3.0 * c + d), 6.0 * (a - 2.0 * b + c), 3.0 * (b - a));
+    let roots = if q.abs() < 1e-12 {
+        if r.abs() < 1e-12 {
+            Vec::new()
+        } else {
+            vec![-s / r]
+        }
+    } else {
+        let discriminant = r * r - 4.0 * q * s;
+        if discriminant < 0.0 {
+            Vec::new()
+        } else {
+            let root = discriminant.sqrt();
+            vec![(-r + root) / (2.0 * q), (-r - root) / (2.0 * q)]
+        }
+    };
+    roots.into_iter().filter(|t| *t > 0.0 && *t < 1.0).collect()
+}
+
+/// Copy of a path in absolute coordinates that only uses moves, lines, cubic
+/// and quadratic curves, arcs and closes: horizontal and vertical lines
+/// become lines, and smooth curves are given their reflected control point.
+pub fn simplify(path: &Path) -> Path {
+    use svgdom::PathSegment::*;
+
+    let mut output = Path::with_capacity(path.len());
+    let (mut cx, mut cy) = (0.0, 0.0);
+    let (mut sx, mut sy) = (0.0, 0.0);
+    // Last control point of a previous cubic (`true`) or quadratic curve.
+    let mut control: Option<(bool, f64, f64)> = None;
+    for i in absolute(path).iter() {
+        let segment = match *i {
+            HorizontalLineTo { abs, x } => LineTo { abs, x, y: cy },
+            VerticalLineTo { abs, y } => LineTo { abs, x: cx, y },
+            SmoothCurveTo { abs, x2, y2, x, y } => {
+                let (x1, y1) = match control {
+                    Some((true, px, py)) => (2.0 * cx - px, 2.0 * cy - py),
+                    _ => (cx, cy),
+                };
+                CurveTo { abs, x1, y1, x2, y2, x, y }
+            }
+            SmoothQuadratic { abs, x, y } => {
+                let (x1, y1) = match control {
+                    Some((false, px, py)) => (2.0 * cx - px, 2.0 * cy - py),
+                    _ => (cx, cy),
+                };
+                Quadratic { abs, x1, y1, x, y }
+            }
+            segment => segment,
+        };
+
+        control = None;
+        match segment {
+            MoveTo { x, y, .. } => {
+                cx = x;
+                cy = y;
+                sx = x;
+                sy = y;
+            }
+            LineTo { x, y, .. } | EllipticalArc { x, y, .. } => {
+                cx = x;
+                cy = y;
+            }
+            CurveTo { x2, y2, x, y, .. } => {
+                control = Some((true, x2, y2));
+                cx = x;
+                cy = y;
+            }
+            Quadratic { x1, y1, x, y, .. } => {
+                control = Some((false, x1, y1));
+                cx = x;
+                cy = y;
+            }
+            ClosePath { .. } => {
+                cx = sx;
+                cy = sy;
+            }
+            _ => {}
+        }
+        output.push(segment);
+    }
+    output
+}
+
+/// Copy of a path in absolute coordinates that only uses moves, lines, cubic
+/// curves and closes, for outputs that can't draw anything else: quadratic
+/// curves are raised to cubic curves and arcs are approximated by them.
+/// Every contour starts with a move.
+pub fn cubic(path: &Path) -> Path {
+    use svgdom::PathSegment::*;
+
+    let mut output = Path::with_capacity(path.len());
+    let (mut current, mut start) = ((0.0, 0.0), (0.0, 0.0));
+    let mut open = false;
+    for segment in simplify(path).iter() {
+        if !open && !matches!(segment, MoveTo { .. } | ClosePath { .. }) {
+            output.push(MoveTo { abs: true, x: current.0, y: current.1 });
+            start = current;
+            open = true;
+        }
+        match *segment {
+            MoveTo { x, y, .. } => {
+                output.push(*segment);
+                current = (x, y);
+                start = current;
+                open = true;
+            }
+            LineTo { x, y, .. } | CurveTo { x, y, .. } => {
+                output.push(*segment);
+                current = (x, y);
+            }
+            Quadratic { x1, y1, x, y, .. } => {
+                // Control points two thirds of the way to the quadratic's.
+                let raise = |(ax, ay): (f64, f64)| {
+                    (ax + 2.0 / 3.0 * (x1 - ax), ay + 2.0 / 3.0 * (y1 - ay))
+                };
+                let ((c1x, c1y), (c2x, c2y)) = (raise(current), raise((x, y)));
+                output.push(CurveTo { abs: true, x1: c1x, y1: c1y, x2: c2x, y2: c2y, x, y });
+                current = (x, y);
+            }
+            EllipticalArc { rx, ry, x_axis_rotation, large_arc, sweep, x, y, .. } => {
+                let to = (x, y);
+                let curves = arc_to_curves(current, rx, ry, x_axis_rotation, large_arc, sweep, to);
+                for [(x1, y1), (x2, y2), (x, y)] in curves {
+                    output.push(CurveTo { abs: true, x1, y1, x2, y2, x, y });
+                }
+                current = (x, y);
+            }
+            ClosePath { .. } => {
+                if open {
+                    output.push(*segment);
+                }
+                current = start;
+                open = false;
+            }
+            _ => {}
+        }
+    }
+    output
+}
+
+/// Cubic curves approximating an elliptical arc from `from` to `to`, with
+/// the radii, rotation (in degrees) and flags of an SVG arc, as their two
+/// control points and end point.  Each curve covers at most a quarter turn.
+pub fn arc_to_curves(
+    from: (f64, f64),
+    rx: f64,
+    ry: f64,
+    rotation: f64,
+    large_arc: bool,
+    sweep: bool,
+    to: (f64, f64),
+) -> Vec<[(f64, f64); 3]> {
+    use std::f64::consts::PI;
+
+    if from == to {
+        return Vec::new();
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 {
+        return vec![[from, to, to]];
+    }
+
+    // Center parameterization, as in the SVG specification's implementation
+    // notes.
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((from.0 - to.0) / 2.0, (from.1 - to.1) / 2.0);
+    let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+    if large_arc == sweep {
+        coefficient = -coefficient;
+    }
+    let (cx1, cy1) = (coefficient * rx * y1 / ry, -coefficient * ry * x1 / rx);
+    let cx = cos * cx1 - sin * cy1 + (from.0 + to.0) / 2.0;
+    let cy = sin * cx1 + cos * cy1 + (from.1 + to.1) / 2.0;
+
+    let angle = |(ux, uy): (f64, f64), (vx, vy): (f64, f64)| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let start = ((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let end = ((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+    let theta = angle((1.0, 0.0), start);
+    let mut delta = angle(start, end);
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    }
+
+    let point = |t: f64| {
+        let (s, c) = t.sin_cos();
+        (cx + rx * c * cos - ry * s * sin, cy + rx * c * sin + ry * s * cos)
+    };
+    let tangent = |t: f64| {
+        let (s, c) = t.sin_cos();
+        (-rx * s * cos - ry * c * sin, -rx * s * sin + ry * c * cos)
+    };
+    let count = (delta.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+    let step = delta / count as f64;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    (0..count)
+        .map(|i| {
+            let (t1, t2) = (theta + step * i as f64, theta + step * (i + 1) as f64);
+            let (p1, d1) = (point(t1), tangent(t1));
+            let (p2, d2) = (point(t2), tangent(t2));
+            let end = if i + 1 == count { to } else { p2 };
+            [(p1.0 + k * d1.0, p1.1 + k * d1.1), (p2.0 - k * d2.0, p2.1 - k * d2.1), end]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(d: &str) -> Path {
+        d.parse().unwrap()
+    }
+
+    #[test]
+    fn extent_measures_curves() {
+        // Control points at y = 100 only pull the curve up to 75.
+        let path = parse("M0 0C0 100 100 100 100 0Z");
+        assert_eq!(bounds(&path), Some([0.0, 0.0, 100.0, 100.0]));
+        assert_eq!(extent(&path), Some([0.0, 0.0, 100.0, 75.0]));
+    }
+}
@@ -0,0 +1,539 @@
+//! Overlap removal and contour direction normalization, so that glyphs fill
+//! the same way under the even-odd and nonzero fill rules.
+//!
+//! Outlines are treated as filled with the nonzero rule (as SVG fills them by
+//! default).  The result has no overlapping contours, outer contours going
+//! clockwise and holes counter-clockwise (with the y axis up, as in the source
+//! fonts).
+
+use svgdom::{Path, PathSegment};
+
+use crate::outline;
+
+type Point = (f64, f64);
+
+/// Distance under which points are taken to be the same, in ScoreFall units.
+const SAME: f64 = 0.01;
+/// Distance under which contour ends are joined, in ScoreFall units.
+const JOIN: f64 = 0.05;
+/// How far either side of an edge to check which side is filled.  Paths are
+/// written rounded to whole units, so this is well below anything visible.
+const SIDE: f64 = 0.5;
+/// Most subdivisions to try when intersecting two curves, which only runs out
+/// for curves that run along each other.
+const BUDGET: usize = 20_000;
+
+/// A cubic curve, or a straight line with control points on it.
+#[derive(Clone, Copy)]
+struct Curve {
+    p: [Point; 4],
+    line: bool,
+}
+
+impl Curve {
+    fn line(a: Point, b: Point) -> Curve {
+        Curve { p: [a, lerp(a, b, 1.0 / 3.0), lerp(a, b, 2.0 / 3.0), b], line: true }
+    }
+
+    fn cubic(p: [Point; 4]) -> Curve {
+        Curve { p, line: false }
+    }
+
+    fn at(&self, t: f64) -> Point {
+        let [a, b, c, d] = self.p;
+        let (ab, bc, cd) = (lerp(a, b, t), lerp(b, c, t), lerp(c, d, t));
+        lerp(lerp(ab, bc, t), lerp(bc, cd, t), t)
+    }
+
+    fn tangent(&self, t: f64) -> Point {
+        let [a, b, c, d] = self.p;
+        let u = 1.0 - t;
+        let x = u * u * (b.0 - a.0) + 2.0 * u * t * (c.0 - b.0) + t * t * (d.0 - c.0);
+        let y = u * u * (b.1 - a.1) + 2.0 * u * t * (c.1 - b.1) + t * t * (d.1 - c.1);
+        if x.hypot(y) < SAME {
+            (d.0 - a.0, d.1 - a.1)
+        } else {
+            (x, y)
+        }
+    }
+
+    fn split(&self, t: f64) -> (Curve, Curve) {
+        let [a, b, c, d] = self.p;
+        let (ab, bc, cd) = (lerp(a, b, t), lerp(b, c, t), lerp(c, d, t));
+        let (abc, bcd) = (lerp(ab, bc, t), lerp(bc, cd, t));
+        let m = lerp(abc, bcd, t);
+        (
+            Curve { p: [a, ab, abc, m], line: self.line },
+            Curve { p: [m, bcd, cd, d], line: self.line },
+        )
+    }
+
+    /// Part of the curve from `t0` to `t1`.
+    fn part(&self, t0: f64, t1: f64) -> Curve {
+        let right = if t0 > 0.0 { self.split(t0).1 } else { *self };
+        if t1 < 1.0 {
+            right.split((t1 - t0) / (1.0 - t0)).0
+        } else {
+            right
+        }
+    }
+
+    fn reversed(&self) -> Curve {
+        let [a, b, c, d] = self.p;
+        Curve { p: [d, c, b, a], line: self.line }
+    }
+
+    fn bounds(&self) -> [f64; 4] {
+        let mut bounds = [self.p[0].0, self.p[0].1, self.p[0].0, self.p[0].1];
+        for p in self.p[1..].iter() {
+            bounds = [bounds[0].min(p.0), bounds[1].min(p.1), bounds[2].max(p.0), bounds[3].max(p.1)];
+        }
+        bounds
+    }
+
+    /// Add points along the curve, close enough together for the polygon
+    /// through them to stay well within `SIDE` of the curve.
+    fn flatten(&self, points: &mut Vec<Point>) {
+        if self.line {
+            points.push(self.p[3]);
+            return;
+        }
+        let length: f64 = self.p.windows(2).map(|w| distance(w[0], w[1])).sum();
+        let count = length.ceil().clamp(1.0, 256.0) as usize;
+        for i in 1..=count {
+            points.push(self.at(i as f64 / count as f64));
+        }
+    }
+}
+
+/// Remove overlaps from a path and give its contours the normal directions.
+pub fn normalize(path: &Path) -> Path {
+    let contours = contours(path);
+    // Points that aren't numbers can't be placed inside or outside anything.
+    let finite = |p: &Point| p.0.is_finite() && p.1.is_finite();
+    if !contours.iter().flatten().all(|curve| curve.p.iter().all(finite)) {
+        return path.clone();
+    }
+    let polygons: Vec<Vec<Point>> = contours
+        .iter()
+        .map(|contour| {
+            let mut points = vec![contour[0].p[0]];
+            for curve in contour.iter() {
+                curve.flatten(&mut points);
+            }
+            points
+        })
+        .collect();
+    let filled = |p: Point| winding(&polygons, p) != 0;
+
+    let curves: Vec<Curve> = contours.iter().flatten().cloned().collect();
+    let normalized = match intersections(&curves) {
+        Some(ref cuts) if cuts.iter().all(|c| c.is_empty()) => Some(orient(contours, &filled)),
+        Some(cuts) => union(&curves, &cuts, &filled),
+        None => None,
+    };
+    // Curves that run along each other, or joins that don't meet up, are left
+    // overlapping, but still get the normal directions.
+    let normalized = normalized.unwrap_or_else(|| orient(self::contours(path), &filled));
+    to_path(&normalized)
+}
+
+/// Split a path into closed contours of curves.
+fn contours(path: &Path) -> Vec<Vec<Curve>> {
+    use svgdom::PathSegment::*;
+
+    let mut contours = Vec::new();
+    let mut contour: Vec<Curve> = Vec::new();
+    let (mut start, mut current) = ((0.0, 0.0), (0.0, 0.0));
+    let close = |contour: &mut Vec<Curve>, contours: &mut Vec<Vec<Curve>>, current, start| {
+        if contour.is_empty() {
+            return;
+        }
+        if distance(current, start) > SAME {
+            contour.push(Curve::line(current, start));
+        }
+        contours.push(std::mem::take(contour));
+    };
+    for segment in outline::simplify(path).iter() {
+        match *segment {
+            MoveTo { x, y, .. } => {
+                close(&mut contour, &mut contours, current, start);
+                start = (x, y);
+                current = start;
+            }
+            LineTo { x, y, .. } => {
+                if distance(current, (x, y)) > SAME {
+                    contour.push(Curve::line(current, (x, y)));
+                }
+                current = (x, y);
+            }
+            CurveTo { x1, y1, x2, y2, x, y, .. } => {
+                contour.push(Curve::cubic([current, (x1, y1), (x2, y2), (x, y)]));
+                current = (x, y);
+            }
+            Quadratic { x1, y1, x, y, .. } => {
+                let c1 = lerp(current, (x1, y1), 2.0 / 3.0);
+                let c2 = lerp((x, y), (x1, y1), 2.0 / 3.0);
+                contour.push(Curve::cubic([current, c1, c2, (x, y)]));
+                current = (x, y);
+            }
+            EllipticalArc { rx, ry, x_axis_rotation, large_arc, sweep, x, y, .. } => {
+                let arc = outline::arc_to_curves(
+                    current,
+                    rx,
+                    ry,
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    (x, y),
+                );
+                for [c1, c2, end] in arc {
+                    contour.push(Curve::cubic([current, c1, c2, end]));
+                    current = end;
+                }
+            }
+            ClosePath { .. } => {
+                close(&mut contour, &mut contours, current, start);
+                current = start;
+            }
+            _ => {}
+        }
+    }
+    close(&mut contour, &mut contours, current, start);
+    contours
+}
+
+/// Where each curve crosses or touches the others, as its curve parameter
+/// and the point it meets the other curve at, sorted by parameter.  `None` if
+/// some curves run along each other.
+fn intersections(curves: &[Curve]) -> Option<Vec<Vec<(f64, Point)>>> {
+    let mut cuts = vec![Vec::new(); curves.len()];
+    let bounds: Vec<[f64; 4]> = curves.iter().map(|c| c.bounds()).collect();
+    for i in 0..curves.len() {
+        for j in i + 1..curves.len() {
+            if !overlap(bounds[i], bounds[j]) {
+                continue;
+            }
+            let (a, b) = (&curves[i], &curves[j]);
+            let mut found = Vec::new();
+            if a.line && b.line {
+                intersect_lines(a, b, &mut found);
+            } else {
+                let mut budget = BUDGET;
+                intersect(a, (0.0, 1.0), b, (0.0, 1.0), 0, &mut budget, &mut found)?;
+            }
+            for (ta, tb, p) in found {
+                // Curves always meet their neighbours at their ends.
+                if distance(p, a.p[0]) > SAME && distance(p, a.p[3]) > SAME {
+                    cuts[i].push((ta, p));
+                }
+                if distance(p, b.p[0]) > SAME && distance(p, b.p[3]) > SAME {
+                    cuts[j].push((tb, p));
+                }
+            }
+        }
+    }
+
+    for cuts in cuts.iter_mut() {
+        cuts.sort_by(|a, b| a.0.total_cmp(&b.0));
+        cuts.dedup_by(|b, a| distance(a.1, b.1) < JOIN);
+    }
+    Some(cuts)
+}
+
+/// Intersect two straight lines, including where they lie along each other.
+fn intersect_lines(a: &Curve, b: &Curve, found: &mut Vec<(f64, f64, Point)>) {
+    let (a0, a1, b0, b1) = (a.p[0], a.p[3], b.p[0], b.p[3]);
+    let da = (a1.0 - a0.0, a1.1 - a0.1);
+    let db = (b1.0 - b0.0, b1.1 - b0.1);
+    let offset = (b0.0 - a0.0, b0.1 - a0.1);
+    let denominator = cross(da, db);
+
+    if denominator.abs() > 1e-9 * distance(a0, a1) * distance(b0, b1) {
+        let ta = cross(offset, db) / denominator;
+        let tb = cross(offset, da) / denominator;
+        if (0.0..=1.0).contains(&ta) && (0.0..=1.0).contains(&tb) {
+            found.push((ta, tb, lerp(a0, a1, ta)));
+        }
+        return;
+    }
+
+    // Parallel: only meet if they're on the same line.
+    if cross(offset, da).abs() / distance(a0, a1) > SAME {
+        return;
+    }
+    let project = |p: Point, (s, e): (Point, Point)| {
+        let d = (e.0 - s.0, e.1 - s.1);
+        ((p.0 - s.0) * d.0 + (p.1 - s.1) * d.1) / (d.0 * d.0 + d.1 * d.1)
+    };
+    for (tb, p) in [(0.0, b0), (1.0, b1)].iter() {
+        let ta = project(*p, (a0, a1));
+        if ta > 0.0 && ta < 1.0 {
+            found.push((ta, *tb, *p));
+        }
+    }
+    for (ta, p) in [(0.0, a0), (1.0, a1)].iter() {
+        let tb = project(*p, (b0, b1));
+        if tb > 0.0 && tb < 1.0 {
+            found.push((*ta, tb, *p));
+        }
+    }
+}
+
+/// Intersect two curves by subdividing them until they're too small to
+/// matter.  `ra` and `rb` are the ranges of the original curves' parameters
+/// that `a` and `b` cover.
+fn intersect(
+    a: &Curve,
+    ra: (f64, f64),
+    b: &Curve,
+    rb: (f64, f64),
+    depth: usize,
+    budget: &mut usize,
+    found: &mut Vec<(f64, f64, Point)>,
+) -> Option<()> {
+    *budget = budget.checked_sub(1)?;
+    let (ba, bb) = (a.bounds(), b.bounds());
+    if !overlap(ba, bb) {
+        return Some(());
+    }
+    let small = |b: [f64; 4]| (b[2] - b[0]).max(b[3] - b[1]) < SAME / 2.0;
+    if depth >= 50 || (small(ba) && small(bb)) {
+        found.push(((ra.0 + ra.1) / 2.0, (rb.0 + rb.1) / 2.0, lerp(a.at(0.5), b.at(0.5), 0.5)));
+        return Some(());
+    }
+
+    let (ma, mb) = ((ra.0 + ra.1) / 2.0, (rb.0 + rb.1) / 2.0);
+    let (a0, a1) = a.split(0.5);
+    let (b0, b1) = b.split(0.5);
+    intersect(&a0, (ra.0, ma), &b0, (rb.0, mb), depth + 1, budget, found)?;
+    intersect(&a0, (ra.0, ma), &b1, (mb, rb.1), depth + 1, budget, found)?;
+    intersect(&a1, (ma, ra.1), &b0, (rb.0, mb), depth + 1, budget, found)?;
+    intersect(&a1, (ma, ra.1), &b1, (mb, rb.1), depth + 1, budget, found)
+}
+
+/// Union of the filled area: split the curves where they meet, keep the
+/// pieces with the filled area on one side only, turned to have it on their
+/// right, and join them back up into contours.
+fn union(
+    curves: &[Curve],
+    cuts: &[Vec<(f64, Point)>],
+    filled: &dyn Fn(Point) -> bool,
+) -> Option<Vec<Vec<Curve>>> {
+    let mut pieces: Vec<Curve> = Vec::new();
+    for (curve, cuts) in curves.iter().zip(cuts.iter()) {
+        let (mut t0, mut p0) = (0.0, curve.p[0]);
+        for &(t1, p1) in cuts.iter().chain(std::iter::once(&(1.0, curve.p[3]))) {
+            if distance(p0, p1) < SAME && t1 - t0 < 1e-3 {
+                continue;
+            }
+            let mut piece = curve.part(t0, t1);
+            piece.p[0] = p0;
+            piece.p[3] = p1;
+            if let Some(piece) = boundary(&piece, filled) {
+                if !pieces.iter().any(|other| same(other, &piece)) {
+                    pieces.push(piece);
+                }
+            }
+            t0 = t1;
+            p0 = p1;
+        }
+    }
+
+    let mut contours = Vec::new();
+    while let Some(first) = pieces.pop() {
+        let start = first.p[0];
+        let mut end = first.p[3];
+        let mut contour = vec![first];
+        while distance(end, start) > JOIN {
+            let (i, _) = pieces
+                .iter()
+                .enumerate()
+                .map(|(i, piece)| (i, distance(piece.p[0], end)))
+                .filter(|(_, d)| *d <= JOIN)
+                .min_by(|a, b| a.1.total_cmp(&b.1))?;
+            let mut piece = pieces.swap_remove(i);
+            piece.p[0] = end;
+            end = piece.p[3];
+            contour.push(piece);
+        }
+        contour.last_mut().unwrap().p[3] = start;
+        contours.push(contour);
+    }
+    Some(contours)
+}
+
+/// Turn each contour to have the filled area on its right, dropping contours
+/// with the filled area on both sides or neither, and copies of another
+/// contour.  Only right for contours that don't cross each other.
+fn orient(contours: Vec<Vec<Curve>>, filled: &dyn Fn(Point) -> bool) -> Vec<Vec<Curve>> {
+    let mut oriented: Vec<Vec<Curve>> = Vec::new();
+    for contour in contours {
+        // Curves along the edge of another contour have the filled area on
+        // both sides, so use the first that doesn't.
+        let reverse = match contour.iter().find_map(|c| Some((c, boundary(c, filled)?))) {
+            Some((curve, oriented)) => distance(curve.p[0], oriented.p[0]) > SAME,
+            None => continue,
+        };
+        let contour: Vec<Curve> = if reverse {
+            contour.iter().rev().map(|c| c.reversed()).collect()
+        } else {
+            contour
+        };
+        let copy = oriented.iter().any(|other| {
+            other.len() == contour.len()
+                && contour.iter().all(|a| other.iter().any(|b| same(a, b)))
+        });
+        if !copy {
+            oriented.push(contour);
+        }
+    }
+    oriented
+}
+
+/// The curve, turned to have the filled area on its right, or `None` if it
+/// isn't on the edge of the filled area.
+fn boundary(curve: &Curve, filled: &dyn Fn(Point) -> bool) -> Option<Curve> {
+    let (x, y) = curve.at(0.5);
+    let (dx, dy) = curve.tangent(0.5);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return None;
+    }
+    let (nx, ny) = (dy / length * SIDE, -dx / length * SIDE);
+    match (filled((x - nx, y - ny)), filled((x + nx, y + ny))) {
+        (false, true) => Some(*curve),
+        (true, false) => Some(curve.reversed()),
+        _ => None,
+    }
+}
+
+/// Nonzero winding number of closed polygons around a point.
+fn winding(polygons: &[Vec<Point>], p: Point) -> i32 {
+    let mut winding = 0;
+    for polygon in polygons.iter() {
+        for i in 0..polygon.len() {
+            let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            let side = cross((b.0 - a.0, b.1 - a.1), (p.0 - a.0, p.1 - a.1));
+            if a.1 <= p.1 {
+                if b.1 > p.1 && side > 0.0 {
+                    winding += 1;
+                }
+            } else if b.1 <= p.1 && side < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
+    winding
+}
+
+fn to_path(contours: &[Vec<Curve>]) -> Path {
+    let mut path = Path::new();
+    for contour in contours.iter() {
+        let (x, y) = contour[0].p[0];
+        path.push(PathSegment::MoveTo { abs: true, x, y });
+        for (i, curve) in contour.iter().enumerate() {
+            let [_, (x1, y1), (x2, y2), (x, y)] = curve.p;
+            if curve.line {
+                // The close draws the last line.
+                if i + 1 != contour.len() {
+                    path.push(PathSegment::LineTo { abs: true, x, y });
+                }
+            } else {
+                path.push(PathSegment::CurveTo { abs: true, x1, y1, x2, y2, x, y });
+            }
+        }
+        path.push(PathSegment::ClosePath { abs: true });
+    }
+    path
+}
+
+/// Whether two curves are the same, to within `JOIN`.
+fn same(a: &Curve, b: &Curve) -> bool {
+    a.p.iter().zip(b.p.iter()).all(|(a, b)| distance(*a, *b) < JOIN)
+}
+
+fn overlap(a: [f64; 4], b: [f64; 4]) -> bool {
+    a[0] <= b[2] + SAME && b[0] <= a[2] + SAME && a[1] <= b[3] + SAME && b[1] <= a[3] + SAME
+}
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn cross(a: Point, b: Point) -> f64 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{metadata_json, source};
+
+    /// Normalize the outline of an SVG font glyph drawn with `d`.
+    fn normalized(d: &str) -> Path {
+        let font = source("base.svg", &[("uniE050", "", d)], metadata_json(""));
+        normalize(&font.glyphs[&0xE050])
+    }
+
+    /// Winding number of a path around each point.
+    fn windings(path: &Path, points: &[Point]) -> Vec<i32> {
+        let polygons: Vec<Vec<Point>> = contours(path)
+            .iter()
+            .map(|contour| {
+                let mut points = vec![contour[0].p[0]];
+                for curve in contour.iter() {
+                    curve.flatten(&mut points);
+                }
+                points
+            })
+            .collect();
+        points.iter().map(|p| winding(&polygons, *p)).collect()
+    }
+
+    #[test]
+    fn overlapping_rectangles_merged() {
+        // Source fonts have 2.5 units to a ScoreFall unit.
+        let path = normalized("M0 0L500 0L500 250L0 250ZM250 125L750 125L750 375L250 375Z");
+        assert_eq!(contours(&path).len(), 1);
+        let inside = [(20.0, 20.0), (150.0, 70.0), (280.0, 140.0)];
+        assert_eq!(windings(&path, &inside), vec![-1, -1, -1]);
+        assert_eq!(windings(&path, &[(280.0, 20.0), (20.0, 140.0)]), vec![0, 0]);
+    }
+
+    #[test]
+    fn nested_holes_kept() {
+        let path = normalized(
+            "M0 0L1000 0L1000 1000L0 1000Z\
+             M250 250L250 750L750 750L750 250Z\
+             M400 400L600 400L600 600L400 600Z",
+        );
+        assert_eq!(contours(&path).len(), 3);
+        let points = [(50.0, 50.0), (150.0, 150.0), (200.0, 200.0), (500.0, 500.0)];
+        assert_eq!(windings(&path, &points), vec![-1, 0, -1, 0]);
+    }
+
+    #[test]
+    fn touching_edges_filled_once() {
+        let path = normalized("M0 0L250 0L250 250L0 250ZM250 0L500 0L500 250L250 250Z");
+        let points = [(50.0, 50.0), (99.0, 50.0), (101.0, 50.0), (150.0, 50.0)];
+        assert_eq!(windings(&path, &points), vec![-1, -1, -1, -1]);
+        assert_eq!(outline::bounds(&path), Some([0.0, 0.0, 200.0, 100.0]));
+    }
+
+    #[test]
+    fn not_a_number_left_alone() {
+        let mut path = Path::new();
+        path.push(PathSegment::MoveTo { abs: true, x: 0.0, y: 0.0 });
+        let (x1, y1, x2, y2) = (f64::NAN, 10.0, 10.0, 10.0);
+        path.push(PathSegment::CurveTo { abs: true, x1, y1, x2, y2, x: 10.0, y: 0.0 });
+        path.push(PathSegment::MoveTo { abs: true, x: 5.0, y: -5.0 });
+        path.push(PathSegment::LineTo { abs: true, x: 10.0, y: 0.0 });
+        path.push(PathSegment::ClosePath { abs: true });
+        assert_eq!(normalize(&path).len(), 5);
+    }
+}
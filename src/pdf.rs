@@ -0,0 +1,332 @@
+//! PDF output: Type 3 fonts with the same outlines as the `.sfff` font, for
+//! the print pipeline's PDF writer to embed.
+//!
+//! Type 3 fonts have single byte character codes, so the glyphs are split
+//! into fonts of up to 256: glyph `i` of the `Glyph` table is code `i % 256`
+//! of font `i / 256`, and its glyph name the `Glyph` variant name.  The fonts
+//! are resources (`/F1`, `/F2`, ...) of the file's page tree, so they're
+//! shared by every page, and `fonts` makes their objects for other PDF
+//! writers.  The pages are a proof sheet, showing every glyph with its name.
+//! The fonts' glyph procedures fill the converted paths, in ScoreFall units
+//! with the y axis up, and their `FontMatrix` makes one em four stave spaces,
+//! as in SMuFL: text set at four times the stave space (in points) has glyphs
+//! at the right size.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use sfff::STAVE_SPACE;
+use svgdom::Path;
+
+use crate::outline;
+
+/// Page size (A4), in points.
+const PAGE: [f64; 2] = [595.0, 842.0];
+
+/// Size text is set at on the proof sheet: a stave space of 5 points.
+const FONT_SIZE: f64 = 20.0;
+
+/// Size of a glyph's cell on the proof sheet, in points.
+const CELL: f64 = 54.0;
+
+/// Most glyphs a Type 3 font can have.
+pub const FONT_GLYPHS: usize = 256;
+
+/// Write the fonts into a PDF file.  `glyphs` are the variant names, SMuFL
+/// codepoints and paths of the glyphs, and `font` the outlines they were made
+/// from.
+pub fn write(
+    file: &str,
+    font_name: &str,
+    glyphs: &[(&str, u16, String)],
+    font: &HashMap<u16, Path>,
+) {
+    // Objects, numbered from 1: the catalog and page tree, the fonts, then
+    // the pages.
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(Vec::new());
+    let (font_objects, font_ids) = fonts(glyphs, font, objects.len() + 1);
+    objects.extend(font_objects);
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec());
+    let label_font = objects.len();
+
+    let pages = proof_sheet(font_name, glyphs);
+    let mut kids = Vec::new();
+    for page in pages.iter() {
+        objects.push(stream(page));
+        let page = format!("<< /Type /Page /Parent 2 0 R /Contents {} 0 R >>", objects.len());
+        objects.push(page.into_bytes());
+        kids.push(format!("{} 0 R", objects.len()));
+    }
+
+    let mut resources = String::new();
+    for (i, id) in font_ids.iter().enumerate() {
+        write!(resources, "/F{} {} 0 R ", i + 1, id).unwrap();
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} /MediaBox [0 0 {} {}] \
+         /Resources << /Font << {}/Label {} 0 R >> >> >>",
+        kids.join(" "),
+        kids.len(),
+        PAGE[0],
+        PAGE[1],
+        resources,
+        label_font
+    )
+    .into_bytes();
+
+    std::fs::write(file, document(&objects)).unwrap_or_else(|e| {
+        eprintln!("Failed to write {}: {}", file, e);
+        std::process::exit(1);
+    });
+}
+
+/// Objects of the Type 3 fonts of `glyphs` (as for `write`), numbered from
+/// `first`, and the object numbers of the fonts.  Each font's objects are
+/// the font dictionary, its `CharProcs` dictionary, then a glyph procedure
+/// for each of its glyphs.
+pub fn fonts(
+    glyphs: &[(&str, u16, String)],
+    font: &HashMap<u16, Path>,
+    first: usize,
+) -> (Vec<Vec<u8>>, Vec<usize>) {
+    let mut objects = Vec::new();
+    let mut ids = Vec::new();
+    for glyphs in glyphs.chunks(FONT_GLYPHS) {
+        let id = first + objects.len();
+        ids.push(id);
+
+        let mut widths = Vec::new();
+        let mut procedures = Vec::new();
+        let mut font_bbox: Option<[f64; 4]> = None;
+        for (_, cp, _) in glyphs {
+            let path = font.get(cp);
+            let bounds = match path.and_then(outline::bounds) {
+                Some([x0, y0, x1, y1]) => [x0.floor(), y0.floor(), x1.ceil(), y1.ceil()],
+                None => [0.0; 4],
+            };
+            if path.is_some() {
+                font_bbox = Some(match font_bbox {
+                    Some(b) => [
+                        b[0].min(bounds[0]),
+                        b[1].min(bounds[1]),
+                        b[2].max(bounds[2]),
+                        b[3].max(bounds[3]),
+                    ],
+                    None => bounds,
+                });
+            }
+            let width = bounds[2].max(0.0);
+            widths.push(width);
+            procedures.push(procedure(path, width, bounds));
+        }
+        let font_bbox = font_bbox.unwrap_or([0.0; 4]);
+
+        let scale = 1.0 / (4.0 * STAVE_SPACE as f64);
+        let mut type3 = String::new();
+        write!(type3, "<< /Type /Font /Subtype /Type3").unwrap();
+        write!(type3, " /FontBBox [{}]", join(&font_bbox)).unwrap();
+        write!(type3, " /FontMatrix [{} 0 0 {} 0 0]", scale, scale).unwrap();
+        write!(type3, " /CharProcs {} 0 R", id + 1).unwrap();
+        write!(type3, " /Encoding << /Type /Encoding /Differences [0").unwrap();
+        for (name, _, _) in glyphs {
+            write!(type3, " /{}", name).unwrap();
+        }
+        write!(type3, "] >>").unwrap();
+        write!(type3, " /FirstChar 0 /LastChar {}", glyphs.len() - 1).unwrap();
+        write!(type3, " /Widths [{}]", join(&widths)).unwrap();
+        write!(type3, " /Resources << >> >>").unwrap();
+        objects.push(type3.into_bytes());
+
+        let mut char_procs = "<<".to_string();
+        for (i, (name, _, _)) in glyphs.iter().enumerate() {
+            write!(char_procs, " /{} {} 0 R", name, id + 2 + i).unwrap();
+        }
+        char_procs.push_str(" >>");
+        objects.push(char_procs.into_bytes());
+        objects.extend(procedures.iter().map(|procedure| stream(procedure)));
+    }
+    (objects, ids)
+}
+
+/// Glyph procedure: the glyph's metrics, then its outline filled.
+fn procedure(path: Option<&Path>, width: f64, bounds: [f64; 4]) -> String {
+    use svgdom::PathSegment::*;
+
+    let mut out = String::new();
+    writeln!(out, "{} 0 {} d1", width, join(&bounds)).unwrap();
+    let path = match path {
+        Some(path) => outline::cubic(path),
+        None => return out,
+    };
+    for segment in path.iter() {
+        match *segment {
+            MoveTo { x, y, .. } => writeln!(out, "{} m", join(&[x, y])),
+            LineTo { x, y, .. } => writeln!(out, "{} l", join(&[x, y])),
+            CurveTo { x1, y1, x2, y2, x, y, .. } => {
+                writeln!(out, "{} c", join(&[x1, y1, x2, y2, x, y]))
+            }
+            ClosePath { .. } => writeln!(out, "h"),
+            _ => Ok(()),
+        }
+        .unwrap();
+    }
+    writeln!(out, "f").unwrap();
+    out
+}
+
+/// Content of the proof sheet pages: each glyph with its name under it.
+fn proof_sheet(font_name: &str, glyphs: &[(&str, u16, String)]) -> Vec<String> {
+    let columns = ((PAGE[0] - CELL) / CELL) as usize;
+    let rows = ((PAGE[1] - 2.0 * CELL) / CELL) as usize + 1;
+    let cell = |i: usize| {
+        let (row, column) = (i / columns, i % columns);
+        (CELL / 2.0 + CELL * column as f64, PAGE[1] - 1.5 * CELL - CELL * row as f64)
+    };
+
+    let indices: Vec<usize> = (0..glyphs.len()).collect();
+    let chunks: Vec<&[usize]> = indices.chunks(columns * rows).collect();
+    let mut pages = Vec::new();
+    for (page, indices) in chunks.iter().enumerate() {
+        let mut out = String::new();
+        writeln!(out, "BT").unwrap();
+        let (x, y) = (CELL / 2.0, PAGE[1] - CELL / 2.0);
+        write!(out, "/Label 12 Tf 1 0 0 1 {} {} Tm (ScoreFall {}", x, y, font_name).unwrap();
+        if chunks.len() > 1 {
+            write!(out, ", page {} of {}", page + 1, chunks.len()).unwrap();
+        }
+        writeln!(out, ") Tj").unwrap();
+        let mut font = None;
+        for (n, i) in indices.iter().enumerate() {
+            if font != Some(i / FONT_GLYPHS) {
+                font = Some(i / FONT_GLYPHS);
+                writeln!(out, "/F{} {} Tf", i / FONT_GLYPHS + 1, FONT_SIZE).unwrap();
+            }
+            let (x, y) = cell(n);
+            let code = i % FONT_GLYPHS;
+            writeln!(out, "1 0 0 1 {} {} Tm <{:02X}> Tj", x + CELL / 4.0, y + CELL / 4.0, code)
+                .unwrap();
+        }
+        writeln!(out, "/Label 4 Tf").unwrap();
+        for (n, i) in indices.iter().enumerate() {
+            let (name, cp, _) = glyphs[*i];
+            let (x, y) = cell(n);
+            writeln!(out, "1 0 0 1 {} {} Tm ({} U+{:04X}) Tj", x, y, name, cp).unwrap();
+        }
+        writeln!(out, "ET").unwrap();
+        pages.push(out);
+    }
+    pages
+}
+
+/// Numbers rounded to whole units, separated by spaces.
+fn join(numbers: &[f64]) -> String {
+    // Adding 0 makes -0 0.
+    let numbers: Vec<String> = numbers.iter().map(|v| (v.round() + 0.0).to_string()).collect();
+    numbers.join(" ")
+}
+
+/// A stream object with `content`.
+fn stream(content: &str) -> Vec<u8> {
+    format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content).into_bytes()
+}
+
+/// A whole PDF file of `objects`; the first is the catalog.
+fn document(objects: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        writeln!(table, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    )
+    .unwrap();
+    out.extend_from_slice(table.as_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::temp_file;
+
+    /// Objects of a PDF file by number, found through its cross-reference
+    /// table.
+    fn objects(pdf: &[u8]) -> Vec<String> {
+        // One character per byte, so offsets stay the same.
+        let text: String = pdf.iter().map(|&b| if b.is_ascii() { b as char } else { '?' }).collect();
+        let start = text.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let xref: usize = text[start..].lines().next().unwrap().parse().unwrap();
+        assert!(text[xref..].starts_with("xref\n0 "));
+        let mut lines = text[xref..].lines().skip(1);
+        let count: usize = lines.next().unwrap()[2..].parse().unwrap();
+        assert_eq!(lines.next(), Some("0000000000 65535 f "));
+        let mut objects = Vec::new();
+        for number in 1..count {
+            let entry = lines.next().unwrap();
+            assert!(entry.ends_with(" 00000 n "));
+            let offset: usize = entry[..10].parse().unwrap();
+            let object = &text[offset..];
+            assert!(object.starts_with(&format!("{} 0 obj\n", number)), "object {}", number);
+            objects.push(object[..object.find("\nendobj\n").unwrap()].to_string());
+        }
+        assert_eq!(lines.next(), Some("trailer"));
+        assert_eq!(lines.next(), Some(format!("<< /Size {} /Root 1 0 R >>", count).as_str()));
+        objects
+    }
+
+    #[test]
+    fn fonts_of_256_glyphs_on_pages() {
+        let file = temp_file("font.pdf");
+        let names: Vec<String> = (0..300).map(|i| format!("Glyph{}", i)).collect();
+        let glyphs: Vec<(&str, u16, String)> = (0..names.len())
+            .map(|i| (names[i].as_str(), 0xE000 + i as u16, String::new()))
+            .collect();
+        let mut font = HashMap::new();
+        font.insert(0xE000, "M0 -50L118 -50L118 50L0 50Z".parse().unwrap());
+        font.insert(0xE000 + 299, "M0 -50L100 -50L100 50L0 50Z".parse().unwrap());
+        write(&file, "Test", &glyphs, &font);
+        let pdf = std::fs::read(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        let objects = objects(&pdf);
+
+        // Catalog, page tree, two fonts (each with its CharProcs and
+        // procedures), Helvetica, and three pages with their contents.
+        assert_eq!(objects.len(), 2 + (2 + 256) + (2 + 44) + 1 + 3 * 2);
+        assert!(objects[1].contains(" /Count 3 "));
+        assert!(objects[1].contains("/Font << /F1 3 0 R /F2 261 0 R /Label 307 0 R >>"));
+        let (first, second) = (&objects[2], &objects[260]);
+        assert!(first.contains("/Subtype /Type3") && second.contains("/Subtype /Type3"));
+        assert!(first.contains("/CharProcs 4 0 R") && second.contains("/CharProcs 262 0 R"));
+        assert!(first.contains("/Differences [0 /Glyph0 /Glyph1 "));
+        assert!(second.contains("/Differences [0 /Glyph256 /Glyph257 "));
+        assert!(first.contains("/LastChar 255 /Widths [118 0 0 "));
+        assert!(second.contains("/LastChar 43 /Widths [0 "));
+        assert!(second.ends_with(" 100] /Resources << >> >>"));
+        assert!(objects[3].starts_with("4 0 obj\n<< /Glyph0 5 0 R /Glyph1 6 0 R "));
+        assert!(objects[4].contains("stream\n118 0 0 -50 118 50 d1\n0 -50 m\n118 -50 l\n"));
+
+        let pages: Vec<&String> = objects.iter().filter(|o| o.contains("/Type /Page ")).collect();
+        assert_eq!(pages.len(), 3);
+        let last = &objects[objects.len() - 2];
+        assert!(last.contains("(ScoreFall Test, page 3 of 3) Tj"));
+        assert!(last.contains("/F2 20 Tf"));
+        assert!(last.contains("<2B> Tj"));
+        assert!(last.contains("(Glyph299 U+E12B) Tj"));
+        assert!(!last.contains("/F1 20 Tf"));
+    }
+}
@@ -0,0 +1,108 @@
+//! The ScoreFall private-use profile: codepoints that ScoreFall gives its own
+//! meaning to, documented in CODEPOINTS.md under "Private Symbols".
+
+use sfff::STAVE_SPACE;
+
+use crate::outline;
+use crate::source::Layered;
+
+/// Repeat Sign Close & Open.
+pub const REPEAT_CLOSE_OPEN: u16 = 0xE042;
+const REPEAT_OPEN: u16 = 0xE040;
+const REPEAT_CLOSE: u16 = 0xE041;
+
+/// Private symbols: codepoint, SMuFL name and meaning in ScoreFall.
+pub const PRIVATE: &[(u16, &str, &str)] = &[
+    (REPEAT_CLOSE_OPEN, "repeatRightLeft", "Repeat Sign Close & Open"),
+    (0xE080, "timeSig0", "Time Signature 0"),
+    (0xE081, "timeSig1", "Time Signature 1"),
+    (0xE082, "timeSig2", "Time Signature 2"),
+    (0xE083, "timeSig3", "Time Signature 3"),
+    (0xE084, "timeSig4", "Time Signature 4"),
+    (0xE085, "timeSig5", "Time Signature 5"),
+    (0xE086, "timeSig6", "Time Signature 6"),
+    (0xE087, "timeSig7", "Time Signature 7"),
+    (0xE088, "timeSig8", "Time Signature 8"),
+    (0xE089, "timeSig9", "Time Signature 9"),
+    (0xE280, "accidentalQuarterToneFlatStein", "1/2 (1 Quarter Tone) Flat"),
+    (0xE281, "accidentalThreeQuarterTonesFlatZimmermann", "3/2 (3 Quarter Tones) Flat"),
+    (0xE282, "accidentalQuarterToneSharpStein", "1/2 (1 Quarter Tone) Sharp"),
+    (0xE283, "accidentalThreeQuarterTonesSharpStein", "3/2 (3 Quarter Tones) Sharp"),
+];
+
+/// Synthesize missing private symbols where possible, then check that the
+/// font provides every one of them, exiting with a message if it doesn't.
+pub fn apply(font: &mut Layered) {
+    if !font.glyphs.contains_key(&REPEAT_CLOSE_OPEN) {
+        if let Some(path) = repeat_close_open(font) {
+            println!("ScoreFall profile: synthesized {:04X}", REPEAT_CLOSE_OPEN);
+            font.glyphs.insert(REPEAT_CLOSE_OPEN, path);
+        }
+    }
+
+    let missing: Vec<&(u16, &str, &str)> = PRIVATE
+        .iter()
+        .filter(|(cp, _, _)| !font.glyphs.contains_key(cp))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    for (cp, _, meaning) in missing {
+        eprintln!("ScoreFall profile: missing {:04X} ({})", cp, meaning);
+    }
+    std::process::exit(1);
+}
+
+/// Join the close repeat sign to the open repeat sign, sharing their thick
+/// barline.
+fn repeat_close_open(font: &Layered) -> Option<svgdom::Path> {
+    let close = font.glyphs.get(&REPEAT_CLOSE)?;
+    let open = font.glyphs.get(&REPEAT_OPEN)?;
+
+    // Right edge of the close repeat sign, which ends with the thick barline.
+    let right = match font.metadata.glyphBBoxes.get("repeatRight") {
+        Some(bbox) => f64::from(bbox.bBoxNE[0]) * STAVE_SPACE as f64,
+        None => outline::bounds(close)?[2],
+    };
+    let thick = match font.metadata.engravingDefaults {
+        Some(ref engraving) => f64::from(engraving.thickBarlineThickness) * STAVE_SPACE as f64,
+        None => 0.0,
+    };
+
+    let mut path = outline::absolute(close);
+    outline::append(&mut path, &outline::transform(open, 1.0, right - thick, 0.0));
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra::Extras;
+    use crate::source::tests::{metadata, source};
+
+    #[test]
+    fn private_symbols_synthesized_and_exported() {
+        let mut glyphs = vec![
+            ("uniE040", "", "M0 0L250 0L250 1000Z"),
+            ("uniE041", "", "M0 0L125 0L125 1000Z"),
+        ];
+        let names: Vec<String> =
+            PRIVATE.iter().skip(1).map(|(cp, _, _)| format!("uni{:04X}", cp)).collect();
+        glyphs.extend(names.iter().map(|name| (name.as_str(), "", "M0 0L250 250Z")));
+        let meta = metadata(&[("repeatRight", [0.0, 0.0, 0.5, 4.0])]);
+        let mut font = Layered::new(source("base.svg", &glyphs, meta));
+        apply(&mut font);
+
+        let ss = STAVE_SPACE as f64;
+        let bounds = outline::bounds(&font.glyphs[&REPEAT_CLOSE_OPEN]).unwrap();
+        assert_eq!(bounds, [0.0, 0.0, 1.5 * ss, 4.0 * ss]);
+
+        let mut extras = Extras::default();
+        extras.add_profile(&font, &[0xE080, 0xE081]);
+        let ids: Vec<u16> = extras.recommended.iter().map(|glyph| glyph.id).collect();
+        assert_eq!(ids.len(), PRIVATE.len() - 2);
+        assert_eq!(ids[0], REPEAT_CLOSE_OPEN);
+        assert!(!ids.contains(&0xE080));
+        assert_eq!(extras.recommended[0].name, "repeatRightLeft");
+    }
+}
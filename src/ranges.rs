@@ -0,0 +1,75 @@
+//! SMuFL glyph ranges, from `ranges.json`.
+
+use std::collections::HashMap;
+
+use serde_json as json;
+
+use crate::glyphnames::GlyphNames;
+use crate::smufl_serde::Range;
+use crate::source::Layered;
+
+/// SMuFL ranges, by name (e.g. "figuredBass").
+pub struct Ranges(HashMap<String, Range>);
+
+impl Ranges {
+    /// Load SMuFL's `ranges.json`, exiting with a message on failure.
+    pub fn load(path: &str) -> Ranges {
+        let data = std::fs::read_to_string(path).unwrap_or_else(|_| {
+            eprintln!("No such file: {}", path);
+            std::process::exit(1);
+        });
+        Ranges::parse(path, &data)
+    }
+
+    /// Read ranges from the text of the file `path`.
+    pub fn parse(path: &str, data: &str) -> Ranges {
+        let ranges = json::from_str(data).unwrap_or_else(|e| {
+            eprintln!("Failed to parse ranges {}: {}", path, e);
+            std::process::exit(1);
+        });
+
+        Ranges(ranges)
+    }
+
+    /// Find a range by name or by description (e.g. "Figured bass"),
+    /// exiting with a message if there's no such range.
+    pub fn find(&self, range: &str) -> (&String, &Range) {
+        self.0
+            .iter()
+            .find(|(name, r)| {
+                *name == range || r.description.eq_ignore_ascii_case(range)
+            })
+            .unwrap_or_else(|| {
+                eprintln!("No such SMuFL range: {}", range);
+                std::process::exit(1);
+            })
+    }
+
+    /// Get every range, in codepoint order.
+    pub fn sorted(&self) -> Vec<(&String, &Range)> {
+        let mut ranges: Vec<(&String, &Range)> = self.0.iter().collect();
+        ranges.sort_by(|a, b| a.1.range_start.cmp(&b.1.range_start));
+        ranges
+    }
+
+    /// Print how many glyphs of each range the font supplies.
+    pub fn report(&self, font: &Layered, names: &GlyphNames, ranges: &[String]) {
+        for range in ranges {
+            let (_, range) = self.find(range);
+            let supplied = range
+                .glyphs
+                .iter()
+                .filter_map(|name| names.codepoint(name))
+                .filter(|cp| font.glyphs.contains_key(cp))
+                .count();
+            println!(
+                "{} ({}–{}): {} of {} glyphs",
+                range.description,
+                range.range_start,
+                range.range_end,
+                supplied,
+                range.glyphs.len(),
+            );
+        }
+    }
+}
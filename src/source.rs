@@ -0,0 +1,592 @@
+//! Loading SMuFL SVG fonts, and layering several of them into one font.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use sfff::STAVE_SPACE;
+use svgdom::{AttributeId, AttributeValue, Document, ElementId, FilterSvg, Path};
+use serde_json as json;
+
+use crate::lilypond;
+use crate::outline;
+use crate::smufl_serde::{GlyphBBox, SMuFLMetadata};
+use crate::unicode;
+
+/// A SMuFL SVG font together with its JSON metadata.
+pub struct Source {
+    /// File name of the SVG font, used in reports.
+    pub name: String,
+    /// Glyph outlines by codepoint, scaled to ScoreFall units (y still up).
+    pub glyphs: HashMap<u16, Path>,
+    /// Ligature outlines by the sequence of component codepoints they replace.
+    pub ligatures: HashMap<Vec<u16>, Path>,
+    /// Codepoint of the base glyph for each alternate glyph's codepoint.
+    pub alternates: HashMap<u16, u16>,
+    /// Braces of every size, from a LilyPond brace font, to be fitted to the
+    /// SMuFL brace and its alternates.
+    pub braces: Vec<Path>,
+    /// SMuFL metadata for the font, if any was supplied.
+    pub metadata: Option<SMuFLMetadata>,
+}
+
+impl Source {
+    /// Load an SVG font and its metadata, exiting with a message on failure.
+    pub fn load(svg: &str, meta: &str) -> Source {
+        let metadata = std::fs::read_to_string(meta).unwrap_or_else(|_| {
+            eprintln!("No such file: {}", meta);
+            std::process::exit(1);
+        });
+        let metadata: SMuFLMetadata = json::from_str(&metadata).unwrap_or_else(|e| {
+            eprintln!("Failed to parse metadata {}: {}", meta, e);
+            std::process::exit(1);
+        });
+
+        let mut source = Source::load_svg(svg);
+        source.metadata = Some(metadata);
+        source
+    }
+
+    /// Load an SVG font that has no metadata of its own (e.g. a brace font).
+    /// Paths are scaled to ScoreFall units.
+    pub fn load_svg(svg: &str) -> Source {
+        let svg_data = std::fs::read_to_string(svg).unwrap_or_else(|_| {
+            eprintln!("No such file: {}", svg);
+            std::process::exit(1);
+        });
+        Source::parse_svg(svg, &svg_data)
+    }
+
+    /// Read an SVG font from the text of the file `name`.
+    fn parse_svg(name: &str, svg_data: &str) -> Source {
+        let font = Document::from_str(svg_data).unwrap_or_else(|_| {
+            eprintln!("Failed to load font file!");
+            std::process::exit(1);
+        });
+
+        let mut source = Source {
+            name: name.to_string(),
+            glyphs: HashMap::new(),
+            ligatures: HashMap::new(),
+            alternates: HashMap::new(),
+            braces: Vec::new(),
+            metadata: None,
+        };
+        let (scalar, musical) = source.read_glyphs(&font);
+        // Fonts that only provide the Unicode Musical Symbols block fill in
+        // the equivalent SMuFL codepoints.
+        for (cp, path) in musical {
+            source.glyphs.entry(cp).or_insert(path);
+        }
+        if source.glyphs.is_empty() && source.ligatures.is_empty() && source.braces.is_empty() {
+            eprintln!("{}: no glyphs with SMuFL (uniXXXX) or LilyPond names", name);
+            std::process::exit(1);
+        }
+        let paths = source.glyphs.values_mut().chain(source.ligatures.values_mut());
+        for path in paths.chain(source.braces.iter_mut()) {
+            *path = scale(path, scalar);
+        }
+        source
+    }
+
+    /// Give the braces of a LilyPond brace font the codepoints of the SMuFL
+    /// brace and the alternates `metadata` lists for it.
+    fn fit_braces(&mut self, metadata: &SMuFLMetadata) {
+        let braces = std::mem::take(&mut self.braces);
+        for (cp, base, path) in lilypond::fit_braces(&braces, metadata) {
+            self.glyphs.insert(cp, path);
+            if let Some(base) = base {
+                self.alternates.insert(cp, base);
+            }
+        }
+    }
+
+    /// Build hashmaps of svg font document paths, returning the scalar to
+    /// convert them to ScoreFall units, and the Unicode Musical Symbols by
+    /// their SMuFL equivalent codepoint.
+    fn read_glyphs(&mut self, font: &Document) -> (f64, HashMap<u16, Path>) {
+        let iter = font.root().descendants().svg();
+        let mut scalar = None;
+        let mut musical = HashMap::new();
+        for (id, node) in iter {
+            let attrs = node.attributes();
+            match id {
+                ElementId::FontFace => {
+                    // Get units per "em" (Actually units per 5 line stave).
+                    let upe = match attrs.get_value(AttributeId::UnitsPerEm) {
+                        Some(AttributeValue::String(upe)) => upe.parse::<f64>().ok(),
+                        _ => None,
+                    };
+                    let upe = upe.filter(|upe| *upe > 0.0).unwrap_or_else(|| {
+                        eprintln!("{}: bad font-face/units-per-em", self.name);
+                        std::process::exit(1);
+                    });
+                    // Get units per "ss" (Stave Space).
+                    let upss = upe * 0.25;
+                    // Calculate scalar value for paths.
+                    scalar = Some(STAVE_SPACE as f64 / upss);
+                }
+                ElementId::Glyph => {
+                    // Glyph needs a name and path.
+                    let name = if let Some(AttributeValue::String(name)) =
+                        attrs.get_value(AttributeId::GlyphName)
+                    {
+                        name
+                    } else {
+                        continue;
+                    };
+                    let path = if let Some(AttributeValue::Path(path)) =
+                        attrs.get_value(AttributeId::D)
+                    {
+                        path.clone()
+                    } else {
+                        continue;
+                    };
+                    let unicode = if let Some(AttributeValue::String(unicode)) =
+                        attrs.get_value(AttributeId::Unicode)
+                    {
+                        let mut chars = unicode.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) if (c as u32) <= 0xFFFF => Some(c as u16),
+                            (Some(c), None) => {
+                                if let Some(cp) = unicode::to_smufl(c as u32) {
+                                    musical.insert(cp, path);
+                                }
+                                continue;
+                            }
+                            _ => None,
+                        }
+                    } else {
+                        None
+                    };
+                    let name = if name.starts_with("uni") {
+                        let name = name.trim_start_matches("uni");
+                        if let Some(dot) = name.find('.') {
+                            // Alternate "uniXXXX.salt01", at an optional codepoint.
+                            let base = u16::from_str_radix(&name[..dot], 16);
+                            if let (Ok(base), Some(cp)) = (base, unicode) {
+                                self.glyphs.insert(cp, path);
+                                self.alternates.insert(cp, base);
+                            }
+                            continue;
+                        }
+                        if name.contains("uni") {
+                            // Ligature of multiple unicode combined, "uniXXXX_uniYYYY".
+                            let components = name
+                                .split('_')
+                                .map(|c| u16::from_str_radix(c.trim_start_matches("uni"), 16))
+                                .collect::<Result<Vec<u16>, _>>();
+                            if let Ok(components) = components {
+                                self.ligatures.insert(components, path);
+                            }
+                            continue;
+                        }
+                        u16::from_str_radix(name, 16).unwrap()
+                    } else if let Some(cp) = lilypond::codepoint(name) {
+                        // Fonts made for LilyPond (e.g. Scorlatti) use their
+                        // own codepoints, so are mapped by name instead.
+                        cp
+                    } else if lilypond::is_brace(name) {
+                        self.braces.push(path);
+                        continue;
+                    } else {
+                        continue;
+                    };
+
+                    self.glyphs.insert(name, path);
+                }
+                ElementId::Hkern => {
+//                println!("Hkern");
+                }
+                _ => {}
+            }
+        }
+
+        let scalar = scalar.unwrap_or_else(|| {
+            eprintln!("{}: missing font-face/units-per-em", self.name);
+            std::process::exit(1);
+        });
+        (scalar, musical)
+    }
+}
+
+/// Scale every coordinate of a path (arc rotation is an angle, so it's kept).
+pub fn scale(path: &Path, scalar: f64) -> Path {
+    use svgdom::PathSegment::*;
+
+    let mut output = Path::with_capacity(path.len());
+    for i in path.iter() {
+        output.push(match *i {
+            MoveTo { abs, x, y } => MoveTo { abs, x: x * scalar, y: y * scalar },
+            LineTo { abs, x, y } => LineTo { abs, x: x * scalar, y: y * scalar },
+            HorizontalLineTo { abs, x } => HorizontalLineTo { abs, x: x * scalar },
+            VerticalLineTo { abs, y } => VerticalLineTo { abs, y: y * scalar },
+            CurveTo { abs, x1, y1, x2, y2, x, y } => CurveTo {
+                abs,
+                x1: x1 * scalar,
+                y1: y1 * scalar,
+                x2: x2 * scalar,
+                y2: y2 * scalar,
+                x: x * scalar,
+                y: y * scalar,
+            },
+            SmoothCurveTo { abs, x2, y2, x, y } => SmoothCurveTo {
+                abs,
+                x2: x2 * scalar,
+                y2: y2 * scalar,
+                x: x * scalar,
+                y: y * scalar,
+            },
+            Quadratic { abs, x1, y1, x, y } => Quadratic {
+                abs,
+                x1: x1 * scalar,
+                y1: y1 * scalar,
+                x: x * scalar,
+                y: y * scalar,
+            },
+            SmoothQuadratic { abs, x, y } => SmoothQuadratic { abs, x: x * scalar, y: y * scalar },
+            EllipticalArc { abs, rx, ry, x_axis_rotation, large_arc, sweep, x, y } => EllipticalArc {
+                abs,
+                rx: rx * scalar,
+                ry: ry * scalar,
+                x_axis_rotation,
+                large_arc,
+                sweep,
+                x: x * scalar,
+                y: y * scalar,
+            },
+            ClosePath { abs } => ClosePath { abs },
+        });
+    }
+    output
+}
+
+/// Bounding box of an outline, in stave spaces, as SMuFL metadata gives it.
+pub fn measure(path: &Path) -> Option<GlyphBBox> {
+    let [x0, y0, x1, y1] = outline::extent(path)?;
+    let ss = |v: f64| (v / STAVE_SPACE as f64) as f32;
+    Some(GlyphBBox { bBoxSW: [ss(x0), ss(y0)], bBoxNE: [ss(x1), ss(y1)] })
+}
+
+/// A base font with override fonts layered on top of it, and supplement fonts
+/// filling in the codepoints it doesn't have.
+pub struct Layered {
+    /// Names of the sources, base first.
+    pub sources: Vec<String>,
+    /// Glyph outlines by codepoint, from the highest priority source.
+    pub glyphs: HashMap<u16, Path>,
+    /// Ligature outlines by component codepoints, from the highest priority
+    /// source.
+    pub ligatures: HashMap<Vec<u16>, Path>,
+    /// Codepoint of the base glyph for each alternate glyph's codepoint.
+    pub alternates: HashMap<u16, u16>,
+    /// Index into `sources` of the source each glyph was taken from.
+    pub origin: HashMap<u16, usize>,
+    /// Indices into `sources` of every source providing each codepoint.
+    pub providers: HashMap<u16, Vec<usize>>,
+    /// Metadata with per-glyph entries replaced by the overrides.
+    pub metadata: SMuFLMetadata,
+}
+
+impl Layered {
+    /// Start with a base font, which must have metadata.
+    pub fn new(mut base: Source) -> Layered {
+        let name = std::mem::take(&mut base.name);
+        let metadata = base.metadata.take().unwrap_or_else(|| {
+            eprintln!("{}: base font needs metadata", name);
+            std::process::exit(1);
+        });
+        base.fit_braces(&metadata);
+        let origin = base.glyphs.keys().map(|cp| (*cp, 0)).collect();
+        let providers = base.glyphs.keys().map(|cp| (*cp, vec![0])).collect();
+
+        Layered {
+            sources: vec![name],
+            glyphs: base.glyphs,
+            ligatures: base.ligatures,
+            alternates: base.alternates,
+            origin,
+            providers,
+            metadata,
+        }
+    }
+
+    /// Replace glyphs, anchors and bounding boxes with those from `source`.
+    /// Engraving defaults are always kept from the base font.
+    pub fn overlay(&mut self, mut source: Source) {
+        let index = self.add(source.name.clone(), source.metadata.take(), true);
+        source.fit_braces(&self.metadata);
+        for (cp, path) in source.glyphs {
+            self.providers.entry(cp).or_default().push(index);
+            self.glyphs.insert(cp, path);
+            self.origin.insert(cp, index);
+        }
+        self.ligatures.extend(source.ligatures);
+        self.alternates.extend(source.alternates);
+    }
+
+    /// Add glyphs from `source` only where no earlier source provides them.
+    pub fn supplement(&mut self, mut source: Source) {
+        let index = self.add(source.name.clone(), source.metadata.take(), false);
+        source.fit_braces(&self.metadata);
+        for (cp, path) in source.glyphs {
+            self.providers.entry(cp).or_default().push(index);
+            if let Entry::Vacant(entry) = self.glyphs.entry(cp) {
+                entry.insert(path);
+                self.origin.insert(cp, index);
+            }
+        }
+        for (components, path) in source.ligatures {
+            self.ligatures.entry(components).or_insert(path);
+        }
+        for (cp, base) in source.alternates {
+            self.alternates.entry(cp).or_insert(base);
+        }
+    }
+
+    /// Add a source name and merge in its metadata, returning its index.
+    fn add(&mut self, name: String, meta: Option<SMuFLMetadata>, replace: bool) -> usize {
+        let index = self.sources.len();
+        self.sources.push(name);
+
+        if let Some(meta) = meta {
+            let base = &mut self.metadata;
+            merge(&mut base.glyphsWithAnchors, meta.glyphsWithAnchors, replace);
+            merge(&mut base.glyphBBoxes, meta.glyphBBoxes, replace);
+            merge(&mut base.glyphsWithAlternates, meta.glyphsWithAlternates, replace);
+            merge(&mut base.ligatures, meta.ligatures, replace);
+            merge(&mut base.optionalGlyphs, meta.optionalGlyphs, replace);
+            merge(&mut base.sets, meta.sets, replace);
+        }
+
+        index
+    }
+
+    /// Codepoints provided by more than one source, in ascending order.
+    pub fn conflicts(&self) -> Vec<u16> {
+        let mut conflicts: Vec<u16> = self
+            .providers
+            .iter()
+            .filter(|(_, p)| p.len() > 1)
+            .map(|(cp, _)| *cp)
+            .collect();
+        conflicts.sort();
+        conflicts
+    }
+
+    /// Print how many glyphs were taken from each source and which of the
+    /// `used` codepoints they include, and which codepoints were provided by
+    /// more than one source.
+    pub fn report(&self, used: &[u16]) {
+        if self.sources.len() == 1 {
+            return;
+        }
+        for (index, name) in self.sources.iter().enumerate() {
+            let taken = self.origin.values().filter(|i| **i == index).count();
+            let from: Vec<String> = used
+                .iter()
+                .filter(|cp| self.origin.get(cp) == Some(&index))
+                .map(|cp| format!("{:04X}", cp))
+                .collect();
+            println!("{} ({} glyphs, {} used): {}", name, taken, from.len(), from.join(" "));
+        }
+
+        let conflicts = self.conflicts();
+        if conflicts.is_empty() {
+            return;
+        }
+        println!("{} conflicting codepoints:", conflicts.len());
+        for cp in conflicts {
+            let providers: Vec<&str> = self.providers[&cp]
+                .iter()
+                .map(|i| self.sources[*i].as_str())
+                .collect();
+            let used = if used.contains(&cp) { " (used)" } else { "" };
+            println!(
+                "  {:04X}{}: {} wins over {}",
+                cp,
+                used,
+                self.sources[self.origin[&cp]],
+                providers
+                    .iter()
+                    .filter(|name| **name != self.sources[self.origin[&cp]])
+                    .cloned()
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+            );
+        }
+    }
+}
+
+/// Merge per-glyph metadata entries, replacing existing ones if `replace`.
+fn merge<T>(base: &mut HashMap<String, T>, other: HashMap<String, T>, replace: bool) {
+    for (name, value) in other {
+        if replace || !base.contains_key(&name) {
+            base.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// An SVG font with 1000 units per em (4 stave spaces) and the glyphs
+    /// given as (glyph-name, unicode, path).
+    pub(crate) fn svg_font(glyphs: &[(&str, &str, &str)]) -> String {
+        let mut svg = String::from(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\"><defs><font>\
+             <font-face units-per-em=\"1000\"/>",
+        );
+        for (name, unicode, d) in glyphs {
+            svg.push_str(&format!(
+                "<glyph glyph-name=\"{}\" unicode=\"{}\" d=\"{}\"/>",
+                name, unicode, d
+            ));
+        }
+        svg.push_str("</font></defs></svg>");
+        svg
+    }
+
+    /// Metadata with the given top-level JSON `fields`, and every other
+    /// required field empty.
+    pub(crate) fn metadata_json(fields: &str) -> SMuFLMetadata {
+        let mut meta: json::Value = json::from_str(&format!("{{{}}}", fields)).unwrap();
+        let defaults = json::json!({
+            "fontName": "Test",
+            "fontVersion": 1.0,
+            "glyphsWithAnchors": {},
+            "glyphsWithAlternates": {},
+            "glyphBBoxes": {},
+            "ligatures": {},
+            "sets": {},
+            "optionalGlyphs": {},
+        });
+        for (key, value) in defaults.as_object().unwrap() {
+            meta.as_object_mut().unwrap().entry(key.clone()).or_insert(value.clone());
+        }
+        json::from_value(meta).unwrap()
+    }
+
+    /// Metadata with a bounding box (in stave spaces) for each named glyph.
+    pub(crate) fn metadata(bboxes: &[(&str, [f32; 4])]) -> SMuFLMetadata {
+        let bboxes: Vec<String> = bboxes
+            .iter()
+            .map(|(name, b)| {
+                format!(
+                    "\"{}\": {{\"bBoxSW\": [{}, {}], \"bBoxNE\": [{}, {}]}}",
+                    name, b[0], b[1], b[2], b[3]
+                )
+            })
+            .collect();
+        metadata_json(&format!("\"glyphBBoxes\": {{{}}}", bboxes.join(", ")))
+    }
+
+    /// Path of a file in the repository.
+    pub(crate) fn repo_file(file: &str) -> String {
+        format!("{}/{}", env!("CARGO_MANIFEST_DIR"), file)
+    }
+
+    /// Path of a scratch file for a test, in the system's temporary directory.
+    pub(crate) fn temp_file(name: &str) -> String {
+        let file = format!("scorefall-font-{}-{}", std::process::id(), name);
+        std::env::temp_dir().join(file).to_str().unwrap().to_string()
+    }
+
+    /// Bravura, with its metadata.
+    pub(crate) fn bravura() -> Source {
+        Source::load(&repo_file("svg/bravura.svg"), &repo_file("font-main/meta.json"))
+    }
+
+    /// Whether the outline at `codepoint` has the bounding box the metadata
+    /// gives the glyph `name`, and no other glyph's fits it better, so that
+    /// `name` is the glyph at `codepoint`.
+    pub(crate) fn has_bbox(font: &Source, name: &str, codepoint: u16) -> bool {
+        let meta = font.metadata.as_ref().unwrap();
+        let (path, bbox) = match (font.glyphs.get(&codepoint), meta.glyphBBoxes.get(name)) {
+            (Some(path), Some(bbox)) => (path, bbox),
+            _ => return false,
+        };
+        let extent = crate::outline::extent(path).unwrap();
+        let ss = STAVE_SPACE as f64;
+        let error = |bbox: &GlyphBBox| {
+            let expected = [bbox.bBoxSW[0], bbox.bBoxSW[1], bbox.bBoxNE[0], bbox.bBoxNE[1]];
+            let errors = extent.iter().zip(expected.iter());
+            errors.map(|(a, b)| (a / ss - f64::from(*b)).abs()).fold(0.0, f64::max)
+        };
+        let best = error(bbox);
+        best < 0.02 && meta.glyphBBoxes.values().all(|other| error(other) >= best)
+    }
+
+    pub(crate) fn source(
+        name: &str,
+        glyphs: &[(&str, &str, &str)],
+        meta: SMuFLMetadata,
+    ) -> Source {
+        let mut source = Source::parse_svg(name, &svg_font(glyphs));
+        source.metadata = Some(meta);
+        source
+    }
+
+    #[test]
+    fn scales_to_stave_spaces() {
+        let source = Source::parse_svg("a.svg", &svg_font(&[("uniE050", "", "M0 0L250 500Z")]));
+        let bounds = crate::outline::bounds(&source.glyphs[&0xE050]).unwrap();
+        let ss = STAVE_SPACE as f64;
+        assert_eq!(bounds, [0.0, 0.0, ss, 2.0 * ss]);
+    }
+
+    #[test]
+    fn override_replaces_glyphs_and_metadata() {
+        let base = source(
+            "base.svg",
+            &[("uniE050", "", "M0 0L250 0Z"), ("uniE062", "", "M0 0L250 0Z")],
+            metadata(&[("gClef", [0.0, 0.0, 1.0, 0.0]), ("fClef", [0.0, 0.0, 1.0, 0.0])]),
+        );
+        let over = source(
+            "over.svg",
+            &[("uniE050", "", "M0 0L500 0Z")],
+            metadata(&[("gClef", [0.0, 0.0, 2.0, 0.0])]),
+        );
+        let mut font = Layered::new(base);
+        font.overlay(over);
+
+        let right = |cp| crate::outline::bounds(&font.glyphs[&cp]).unwrap()[2];
+        assert_eq!(right(0xE050), 2.0 * STAVE_SPACE as f64);
+        assert_eq!(right(0xE062), STAVE_SPACE as f64);
+        assert_eq!(font.origin[&0xE050], 1);
+        assert_eq!(font.origin[&0xE062], 0);
+        assert_eq!(font.metadata.glyphBBoxes["gClef"].bBoxNE, [2.0, 0.0]);
+        assert_eq!(font.metadata.glyphBBoxes["fClef"].bBoxNE, [1.0, 0.0]);
+        assert_eq!(font.conflicts(), vec![0xE050]);
+    }
+
+    #[test]
+    fn supplements_scorlatti_braces() {
+        let base = Source::load(
+            &repo_file("svg/scorlatti-26.svg"),
+            &repo_file("font-main/meta.json"),
+        );
+        let mut font = Layered::new(base);
+        font.supplement(Source::load_svg(&repo_file("svg/scorlatti-brace.svg")));
+
+        // Glyphs are mapped by their LilyPond names, not Scorlatti's own
+        // codepoints (`rests.0` is at U+E100).
+        assert_eq!(font.origin[&0xE4E3], 0);
+        assert_eq!(font.origin[&0xE050], 0);
+        assert!(!font.glyphs.contains_key(&0xE100));
+        assert!(!font.glyphs.contains_key(&0xE33F));
+
+        // The braces are fitted to the brace and its alternates.
+        for cp in [0xE000, 0xF400, 0xF401, 0xF402].iter() {
+            assert_eq!(font.origin[cp], 1);
+        }
+        for cp in [0xF400, 0xF401, 0xF402].iter() {
+            assert_eq!(font.alternates[cp], 0xE000);
+        }
+        let extent = crate::outline::extent(&font.glyphs[&0xE000]).unwrap();
+        let ss = STAVE_SPACE as f64;
+        assert!((extent[1] / ss - 0.0).abs() < 0.01);
+        assert!((extent[3] / ss - 3.988).abs() < 0.01);
+        assert!(font.conflicts().is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+//! SVG sprite sheet output, for HTML pages to `<use href="#clefG">` glyphs.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use svgdom::Path;
+
+use crate::outline;
+
+/// Write an SVG file with a `<symbol>` for each glyph, with the `Glyph`
+/// variant name in lower camel case as its id, and a view box fitting the
+/// glyph's outline.  `glyphs` are the variant names, SMuFL codepoints and
+/// paths of the glyphs, and `font` the outlines they were made from.
+pub fn write(file: &str, font_name: &str, glyphs: &[(&str, u16, String)], font: &HashMap<u16, Path>) {
+    let mut out = String::new();
+    writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" style="display: none">"#).unwrap();
+    writeln!(out, "  <!-- {} ScoreFall font, generated by scorefall-font. -->", font_name).unwrap();
+    for (name, cp, path) in glyphs {
+        let [x, y, width, height] = view_box(font, *cp);
+        writeln!(
+            out,
+            r#"  <symbol id="{}" viewBox="{} {} {} {}"><path d="{}"/></symbol>"#,
+            crate::lower_camel(name),
+            x,
+            y,
+            width,
+            height,
+            path
+        )
+        .unwrap();
+    }
+    writeln!(out, "</svg>").unwrap();
+
+    std::fs::write(file, out).unwrap_or_else(|e| {
+        eprintln!("Failed to write {}: {}", file, e);
+        std::process::exit(1);
+    });
+}
+
+/// View box fitting the outline of the glyph at `cp` (at the extremes of its
+/// curves, not their control points), as `[x, y, width, height]` in whole
+/// units with the y axis down (as paths are written).
+pub fn view_box(font: &HashMap<u16, Path>, cp: u16) -> [f64; 4] {
+    match font.get(&cp).and_then(outline::extent) {
+        Some([x0, y0, x1, y1]) => {
+            let (x, y) = (x0.floor(), (-y1).floor());
+            [x, y, x1.ceil() - x, (-y0).ceil() - y]
+        }
+        None => [0.0; 4],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::temp_file;
+
+    #[test]
+    fn symbols_fit_glyphs() {
+        let file = temp_file("sprites.svg");
+        let mut font = HashMap::new();
+        font.insert(0xE0A4, "M0 -60L119.6 -60L119.6 40L0 40Z".parse().unwrap());
+        // The curve's control points are 25 units above its top.
+        font.insert(0xE4E5, "M0 0C0 100 100 100 100 0Z".parse().unwrap());
+        let glyphs = [
+            ("NoteheadFill", 0xE0A4, "M0 60L120 60L120 -40L0 -40Z".to_string()),
+            ("Rest4", 0xE4E5, "M0 0C0 -100 100 -100 100 0Z".to_string()),
+            ("Space", 0xE000, String::new()),
+        ];
+        write(&file, "Test", &glyphs, &font);
+        let out = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(
+            out,
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" style=\"display: none\">\n",
+                "  <!-- Test ScoreFall font, generated by scorefall-font. -->\n",
+                "  <symbol id=\"noteheadFill\" viewBox=\"0 -40 120 100\">",
+                "<path d=\"M0 60L120 60L120 -40L0 -40Z\"/></symbol>\n",
+                "  <symbol id=\"rest4\" viewBox=\"0 -75 100 75\">",
+                "<path d=\"M0 0C0 -100 100 -100 100 0Z\"/></symbol>\n",
+                "  <symbol id=\"space\" viewBox=\"0 0 0 0\"><path d=\"\"/></symbol>\n",
+                "</svg>\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Per-score subsetting, for clients to download only the glyphs a score
+//! uses.
+
+/// Load a glyph usage list: `Glyph` variant names (as in `ClefG`, or in lower
+/// camel case as in the web outputs, `clefG`), separated by whitespace or
+/// commas.  Anything after a `#` on a line is a comment.
+pub fn load(file: &str) -> Vec<&'static str> {
+    let data = std::fs::read_to_string(file).unwrap_or_else(|_| {
+        eprintln!("No such file: {}", file);
+        std::process::exit(1);
+    });
+    let table = crate::glyph_table();
+
+    let mut glyphs = Vec::new();
+    let words = data
+        .lines()
+        .map(|line| line.split('#').next().unwrap())
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|word| !word.is_empty());
+    for word in words {
+        let found = table
+            .iter()
+            .map(|(_, name, _, _)| *name)
+            .find(|name| *name == word || crate::lower_camel(name) == word);
+        match found {
+            Some(name) => {
+                if !glyphs.contains(&name) {
+                    glyphs.push(name);
+                }
+            }
+            None => {
+                eprintln!("{}: unknown glyph {}", file, word);
+                std::process::exit(1);
+            }
+        }
+    }
+    glyphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::temp_file;
+
+    #[test]
+    fn variant_names_in_order() {
+        let file = temp_file("glyphs.txt");
+        std::fs::write(&file, "# Used by the score.\nClefG, noteheadFill\n\nClefG # again\n").unwrap();
+        let glyphs = load(&file);
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(glyphs, ["ClefG", "NoteheadFill"]);
+    }
+}
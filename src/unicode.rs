@@ -0,0 +1,177 @@
+//! Equivalences between the Unicode Musical Symbols block (U+1D100–U+1D1FF,
+//! catalogued in CODEPOINTS.md) and SMuFL recommended codepoints.
+
+/// Unicode Musical Symbols codepoints, and their SMuFL equivalents with
+/// their canonical names.
+pub const MUSICAL_SYMBOLS: &[(u32, u16, &str)] = &[
+    // Barlines and repeats
+    (0x1D100, 0xE030, "barlineSingle"),
+    (0x1D101, 0xE031, "barlineDouble"),
+    (0x1D102, 0xE032, "barlineFinal"),
+    (0x1D103, 0xE033, "barlineReverseFinal"),
+    (0x1D104, 0xE036, "barlineDashed"),
+    (0x1D105, 0xE038, "barlineShort"),
+    (0x1D106, 0xE040, "repeatLeft"),
+    (0x1D107, 0xE041, "repeatRight"),
+    (0x1D108, 0xE043, "repeatDots"),
+    (0x1D109, 0xE045, "dalSegno"),
+    (0x1D10A, 0xE046, "daCapo"),
+    (0x1D10B, 0xE047, "segno"),
+    (0x1D10C, 0xE048, "coda"),
+    (0x1D10D, 0xE500, "repeat1Bar"),
+    (0x1D10E, 0xE501, "repeat2Bars"),
+    (0x1D10F, 0xE502, "repeat4Bars"),
+    // Holds and pauses
+    (0x1D110, 0xE4C0, "fermataAbove"),
+    (0x1D111, 0xE4C1, "fermataBelow"),
+    (0x1D112, 0xE4CE, "breathMarkComma"),
+    (0x1D113, 0xE4D1, "caesura"),
+    // Staff brackets, staves and clefs
+    (0x1D114, 0xE000, "brace"),
+    (0x1D115, 0xE002, "bracket"),
+    (0x1D116, 0xE010, "staff1Line"),
+    (0x1D117, 0xE011, "staff2Lines"),
+    (0x1D118, 0xE012, "staff3Lines"),
+    (0x1D119, 0xE013, "staff4Lines"),
+    (0x1D11A, 0xE014, "staff5Lines"),
+    (0x1D11B, 0xE015, "staff6Lines"),
+    (0x1D11C, 0xE856, "fretboard6String"),
+    (0x1D11D, 0xE852, "fretboard4String"),
+    (0x1D11E, 0xE050, "gClef"),
+    (0x1D11F, 0xE053, "gClef8va"),
+    (0x1D120, 0xE052, "gClef8vb"),
+    (0x1D121, 0xE05C, "cClef"),
+    (0x1D122, 0xE062, "fClef"),
+    (0x1D123, 0xE065, "fClef8va"),
+    (0x1D124, 0xE064, "fClef8vb"),
+    (0x1D125, 0xE069, "unpitchedPercussionClef1"),
+    (0x1D126, 0xE06A, "unpitchedPercussionClef2"),
+    // Rests and accidentals
+    (0x1D129, 0xE4EE, "restHBar"),
+    (0x1D12A, 0xE263, "accidentalDoubleSharp"),
+    (0x1D12B, 0xE264, "accidentalDoubleFlat"),
+    (0x1D132, 0xE282, "accidentalQuarterToneSharpStein"),
+    (0x1D133, 0xE280, "accidentalQuarterToneFlatStein"),
+    // Time signatures and octaves
+    (0x1D134, 0xE08A, "timeSigCommon"),
+    (0x1D135, 0xE08B, "timeSigCutCommon"),
+    (0x1D136, 0xE511, "ottavaAlta"),
+    (0x1D137, 0xE512, "ottavaBassa"),
+    (0x1D138, 0xE515, "quindicesimaAlta"),
+    (0x1D139, 0xE516, "quindicesimaBassa"),
+    // Rests
+    (0x1D13A, 0xE4E2, "restDoubleWhole"),
+    (0x1D13B, 0xE4E3, "restWhole"),
+    (0x1D13C, 0xE4E4, "restHalf"),
+    (0x1D13D, 0xE4E5, "restQuarter"),
+    (0x1D13E, 0xE4E6, "rest8th"),
+    (0x1D13F, 0xE4E7, "rest16th"),
+    (0x1D140, 0xE4E8, "rest32nd"),
+    (0x1D141, 0xE4E9, "rest64th"),
+    (0x1D142, 0xE4EA, "rest128th"),
+    // Noteheads
+    (0x1D143, 0xE0A9, "noteheadXBlack"),
+    (0x1D144, 0xE0AF, "noteheadPlusBlack"),
+    (0x1D145, 0xE0B3, "noteheadCircleX"),
+    (0x1D146, 0xE0B8, "noteheadSquareWhite"),
+    (0x1D147, 0xE0B9, "noteheadSquareBlack"),
+    (0x1D148, 0xE0BD, "noteheadTriangleUpWhite"),
+    (0x1D149, 0xE0BE, "noteheadTriangleUpBlack"),
+    (0x1D14A, 0xE0BF, "noteheadTriangleLeftWhite"),
+    (0x1D14B, 0xE0C0, "noteheadTriangleLeftBlack"),
+    (0x1D14C, 0xE0C1, "noteheadTriangleRightWhite"),
+    (0x1D14D, 0xE0C2, "noteheadTriangleRightBlack"),
+    (0x1D14E, 0xE0C6, "noteheadTriangleDownWhite"),
+    (0x1D14F, 0xE0C7, "noteheadTriangleDownBlack"),
+    (0x1D150, 0xE0C8, "noteheadTriangleUpRightWhite"),
+    (0x1D151, 0xE0C9, "noteheadTriangleUpRightBlack"),
+    (0x1D152, 0xE0CA, "noteheadMoonWhite"),
+    (0x1D153, 0xE0CB, "noteheadMoonBlack"),
+    (0x1D154, 0xE0CC, "noteheadTriangleRoundDownWhite"),
+    (0x1D155, 0xE0CD, "noteheadTriangleRoundDownBlack"),
+    (0x1D156, 0xE0CE, "noteheadParenthesis"),
+    (0x1D157, 0xE0A3, "noteheadHalf"),
+    (0x1D158, 0xE0A4, "noteheadBlack"),
+    (0x1D159, 0xE0A5, "noteheadNull"),
+    // Notes, stems and tremolos
+    (0x1D15C, 0xE1D0, "noteDoubleWhole"),
+    (0x1D15D, 0xE1D2, "noteWhole"),
+    (0x1D15E, 0xE1D3, "noteHalfUp"),
+    (0x1D15F, 0xE1D5, "noteQuarterUp"),
+    (0x1D160, 0xE1D7, "note8thUp"),
+    (0x1D161, 0xE1D9, "note16thUp"),
+    (0x1D162, 0xE1DB, "note32ndUp"),
+    (0x1D163, 0xE1DD, "note64thUp"),
+    (0x1D164, 0xE1DF, "note128thUp"),
+    (0x1D165, 0xE210, "stem"),
+    (0x1D166, 0xE211, "stemSprechgesang"),
+    (0x1D167, 0xE220, "tremolo1"),
+    (0x1D168, 0xE221, "tremolo2"),
+    (0x1D169, 0xE222, "tremolo3"),
+    (0x1D16A, 0xE225, "tremoloFingered1"),
+    (0x1D16B, 0xE226, "tremoloFingered2"),
+    (0x1D16C, 0xE227, "tremoloFingered3"),
+    (0x1D16D, 0xE1E7, "augmentationDot"),
+    // Flags
+    (0x1D16E, 0xE240, "flag8thUp"),
+    (0x1D16F, 0xE242, "flag16thUp"),
+    (0x1D170, 0xE244, "flag32ndUp"),
+    (0x1D171, 0xE246, "flag64thUp"),
+    (0x1D172, 0xE248, "flag128thUp"),
+    // Articulations
+    (0x1D17B, 0xE4A0, "articAccentAbove"),
+    (0x1D17C, 0xE4A2, "articStaccatoAbove"),
+    (0x1D17D, 0xE4A4, "articTenutoAbove"),
+    (0x1D17E, 0xE4A6, "articStaccatissimoAbove"),
+    (0x1D17F, 0xE4AC, "articMarcatoAbove"),
+    (0x1D180, 0xE4AE, "articMarcatoStaccatoAbove"),
+    (0x1D181, 0xE4B0, "articAccentStaccatoAbove"),
+    (0x1D183, 0xE634, "arpeggiatoUp"),
+    (0x1D184, 0xE635, "arpeggiatoDown"),
+    // Dynamics
+    (0x1D18C, 0xE523, "dynamicRinforzando"),
+    (0x1D18D, 0xE524, "dynamicSforzando"),
+    (0x1D18E, 0xE525, "dynamicZ"),
+    (0x1D18F, 0xE520, "dynamicPiano"),
+    (0x1D190, 0xE521, "dynamicMezzo"),
+    (0x1D191, 0xE522, "dynamicForte"),
+    (0x1D192, 0xE53E, "dynamicCrescendoHairpin"),
+    (0x1D193, 0xE53F, "dynamicDiminuendoHairpin"),
+    // Grace notes and ornaments
+    (0x1D194, 0xE560, "graceNoteAcciaccaturaStemUp"),
+    (0x1D195, 0xE562, "graceNoteAppoggiaturaStemUp"),
+    (0x1D196, 0xE566, "ornamentTrill"),
+    (0x1D197, 0xE567, "ornamentTurn"),
+    (0x1D198, 0xE568, "ornamentTurnInverted"),
+    // Techniques
+    (0x1D1AC, 0xE614, "stringsHarmonic"),
+    (0x1D1AE, 0xE650, "keyboardPedalPed"),
+    (0x1D1AF, 0xE655, "keyboardPedalUp"),
+];
+
+/// Get the SMuFL equivalent of a Unicode Musical Symbols codepoint.
+pub fn to_smufl(unicode: u32) -> Option<u16> {
+    MUSICAL_SYMBOLS
+        .iter()
+        .find(|(u, _, _)| *u == unicode)
+        .map(|(_, smufl, _)| *smufl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{bravura, has_bbox};
+
+    #[test]
+    fn names_match_bravura() {
+        let font = bravura();
+        // noteheadNull has no outline to measure.
+        let mismatched: Vec<&str> = MUSICAL_SYMBOLS
+            .iter()
+            .filter(|(_, cp, name)| *name != "noteheadNull" && !has_bbox(&font, name, *cp))
+            .map(|(_, _, name)| *name)
+            .collect();
+        assert!(mismatched.is_empty(), "{:?}", mismatched);
+        assert_eq!(to_smufl(0x1D11C), Some(0xE856));
+    }
+}
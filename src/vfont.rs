@@ -0,0 +1,168 @@
+//! Rust source output, for score2svg to compile the font in (see
+//! install.sh).
+
+use std::fmt::Write;
+
+use sfff::{SfFontMetadata, STAVE_SPACE};
+
+/// Write `mod.rs` of the `vfont` module into `dir`: the font metrics, a
+/// `Glyph` enum and a path constant for each glyph.  `glyphs` are the
+/// variant names, SMuFL codepoints and paths of the glyphs.
+pub fn write(dir: &str, metadata: &SfFontMetadata, glyphs: &[(&str, u16, String)]) {
+    let mut out = String::new();
+    writeln!(out, "//! {} ScoreFall font, generated by scorefall-font.", metadata.font_name).unwrap();
+    writeln!(out, "//! Don't edit; regenerate it with install.sh instead.").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "/// Units per stave space.").unwrap();
+    writeln!(out, "pub const STAVE_SPACE: i32 = {};", STAVE_SPACE).unwrap();
+    writeln!(out).unwrap();
+
+    let metrics = metrics(metadata);
+    writeln!(out, "/// Font metrics, in units of `STAVE_SPACE` per stave space.").unwrap();
+    writeln!(out, "pub struct Metrics {{").unwrap();
+    writeln!(out, "    pub font_name: &'static str,").unwrap();
+    for (field, value) in metrics.iter() {
+        let ty = if value.starts_with('[') { "[[i32; 2]; 2]" } else { "i32" };
+        writeln!(out, "    pub {}: {},", field, ty).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "/// Metrics of this font.").unwrap();
+    writeln!(out, "pub const METRICS: Metrics = Metrics {{").unwrap();
+    writeln!(out, "    font_name: {:?},", metadata.font_name).unwrap();
+    for (field, value) in metrics.iter() {
+        writeln!(out, "    {}: {},", field, value).unwrap();
+    }
+    writeln!(out, "}};").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "/// Glyphs in the font.").unwrap();
+    writeln!(out, "#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]").unwrap();
+    writeln!(out, "pub enum Glyph {{").unwrap();
+    for (name, _, _) in glyphs {
+        writeln!(out, "    {},", name).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "impl Glyph {{").unwrap();
+    writeln!(out, "    /// Every glyph, in order.").unwrap();
+    writeln!(out, "    pub const ALL: [Glyph; {}] = [", glyphs.len()).unwrap();
+    for (name, _, _) in glyphs {
+        writeln!(out, "        Glyph::{},", name).unwrap();
+    }
+    writeln!(out, "    ];").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    /// SVG path of the glyph, with the y axis down.").unwrap();
+    writeln!(out, "    pub const fn path(self) -> &'static str {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for (name, _, _) in glyphs {
+        writeln!(out, "            Glyph::{} => {},", name, constant(name)).unwrap();
+    }
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    /// SMuFL codepoint the glyph was taken from.").unwrap();
+    writeln!(out, "    pub const fn codepoint(self) -> u16 {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for (name, cp, _) in glyphs {
+        writeln!(out, "            Glyph::{} => 0x{:04X},", name, cp).unwrap();
+    }
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    for (name, cp, path) in glyphs {
+        writeln!(out).unwrap();
+        writeln!(out, "/// `Glyph::{}` (U+{:04X}).", name, cp).unwrap();
+        writeln!(out, "pub const {}: &str = {:?};", constant(name), path).unwrap();
+    }
+
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(format!("{}/mod.rs", dir), out).unwrap();
+}
+
+/// Metrics fields and their values, as Rust (and JavaScript) source.
+pub fn metrics(m: &SfFontMetadata) -> Vec<(&'static str, String)> {
+    vec![
+        ("stave_line_thickness", m.stave_line_thickness.to_string()),
+        ("stem_thickness", m.stem_thickness.to_string()),
+        ("ledger_line_thickness", m.ledger_line_thickness.to_string()),
+        ("ledger_line_extension", m.ledger_line_extension.to_string()),
+        ("slur_endpoint_thickness", m.slur_endpoint_thickness.to_string()),
+        ("slur_midpoint_thickness", m.slur_midpoint_thickness.to_string()),
+        ("barline_thickness", m.barline_thickness.to_string()),
+        ("thick_barline_thickness", m.thick_barline_thickness.to_string()),
+        ("barlines_space", m.barlines_space.to_string()),
+        ("barline_repeatdot_space", m.barline_repeatdot_space.to_string()),
+        ("bracket_thickness", m.bracket_thickness.to_string()),
+        ("subbracket_thickness", m.subbracket_thickness.to_string()),
+        ("hairpin_thickness", m.hairpin_thickness.to_string()),
+        ("rehearsal_box_thickness", m.rehearsal_box_thickness.to_string()),
+        ("notehead", format!("{:?}", m.notehead)),
+        ("notehead_x", format!("{:?}", m.notehead_x)),
+        ("notehead_diamond", format!("{:?}", m.notehead_diamond)),
+        ("notehead_triangle", format!("{:?}", m.notehead_triangle)),
+        ("notehead_slash", format!("{:?}", m.notehead_slash)),
+        ("notehead_half", format!("{:?}", m.notehead_half)),
+        ("notehead_half_x", format!("{:?}", m.notehead_half_x)),
+        ("notehead_half_diamond", format!("{:?}", m.notehead_half_diamond)),
+        ("notehead_half_triangle", format!("{:?}", m.notehead_half_triangle)),
+        ("notehead_half_slash", format!("{:?}", m.notehead_half_slash)),
+        ("notehead_whole", format!("{:?}", m.notehead_whole)),
+        ("notehead_whole_x", format!("{:?}", m.notehead_whole_x)),
+        ("notehead_whole_diamond", format!("{:?}", m.notehead_whole_diamond)),
+        ("notehead_whole_triangle", format!("{:?}", m.notehead_whole_triangle)),
+        ("notehead_whole_slash", format!("{:?}", m.notehead_whole_slash)),
+        ("notehead_double", format!("{:?}", m.notehead_double)),
+        ("notehead_double_x", format!("{:?}", m.notehead_double_x)),
+        ("notehead_double_diamond", format!("{:?}", m.notehead_double_diamond)),
+        ("notehead_double_triangle", format!("{:?}", m.notehead_double_triangle)),
+        ("notehead_double_slash", format!("{:?}", m.notehead_double_slash)),
+    ]
+}
+
+/// Name of the path constant of a glyph: `NoteheadFillX` => `NOTEHEAD_FILL_X`.
+fn constant(name: &str) -> String {
+    let mut constant = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && previous.is_some_and(|p| p.is_ascii_lowercase()) {
+            constant.push('_');
+        }
+        constant.push(c.to_ascii_uppercase());
+        previous = Some(c);
+    }
+    constant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_json::tests::sf_metadata;
+    use crate::source::tests::temp_file;
+
+    #[test]
+    fn module_source() {
+        let dir = temp_file("vfont");
+        let glyphs = [
+            ("NoteheadFillX", 0xE0A9, "M0 0L10 0Z".to_string()),
+            ("ClefG", 0xE050, "M1 1Z".to_string()),
+        ];
+        write(&dir, &sf_metadata(), &glyphs);
+        let out = std::fs::read_to_string(format!("{}/mod.rs", dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(out.starts_with("//! Test ScoreFall font, generated by scorefall-font.\n"));
+        assert!(out.contains(&format!("pub const STAVE_SPACE: i32 = {};\n", STAVE_SPACE)));
+        assert!(out.contains("    pub stem_thickness: i32,\n"));
+        assert!(out.contains("    pub notehead_x: [[i32; 2]; 2],\n"));
+        assert!(out.contains("    stem_thickness: 11,\n"));
+        assert!(out.contains("    notehead_x: [[0, -50], [101, 50]],\n"));
+        assert!(out.contains("pub enum Glyph {\n    NoteheadFillX,\n    ClefG,\n}\n"));
+        assert!(out.contains("    pub const ALL: [Glyph; 2] = [\n"));
+        assert!(out.contains("            Glyph::NoteheadFillX => NOTEHEAD_FILL_X,\n"));
+        assert!(out.contains("            Glyph::ClefG => 0xE050,\n"));
+        assert!(out.contains("pub const NOTEHEAD_FILL_X: &str = \"M0 0L10 0Z\";\n"));
+        assert!(out.contains("/// `Glyph::ClefG` (U+E050).\npub const CLEF_G: &str = \"M1 1Z\";\n"));
+    }
+}
@@ -0,0 +1,349 @@
+//! Stroke weight adjustment, for deriving a heavier or lighter font from the
+//! same source font.
+
+use sfff::STAVE_SPACE;
+use svgdom::{Path, PathSegment};
+
+use crate::glyphnames::GlyphNames;
+use crate::optical;
+use crate::outline;
+use crate::smufl_serde::{EngravingDefaults, GlyphBBox};
+use crate::source::Layered;
+
+/// Make the strokes of the glyphs at `codepoints` (or of every glyph, if
+/// `None`) `amount` stave spaces heavier, or lighter if it's negative, along
+/// with the ligatures of only those glyphs.  The bounding boxes in the
+/// metadata grow or shrink to match, taking the glyphs' anchors with them.
+/// Returns the number of glyphs (including ligatures) changed.
+pub fn apply(
+    font: &mut Layered,
+    names: &GlyphNames,
+    codepoints: Option<&[u16]>,
+    amount: f32,
+) -> usize {
+    let offset = f64::from(amount) * STAVE_SPACE as f64 / 2.0;
+    let mut changed = 0;
+    for (cp, path) in font.glyphs.iter_mut() {
+        if codepoints.is_none_or(|cps| cps.contains(cp)) {
+            *path = embolden(path, offset);
+            changed += 1;
+        }
+    }
+    for (components, path) in font.ligatures.iter_mut() {
+        if codepoints.is_none_or(|cps| components.iter().all(|cp| cps.contains(cp))) {
+            *path = embolden(path, offset);
+            changed += 1;
+        }
+    }
+
+    let meta = &mut font.metadata;
+    for (name, bbox) in meta.glyphBBoxes.iter_mut() {
+        let included = match codepoints {
+            Some(cps) => names.codepoint(name).is_some_and(|cp| cps.contains(&cp)),
+            None => true,
+        };
+        if !included {
+            continue;
+        }
+        let old = GlyphBBox { bBoxSW: bbox.bBoxSW, bBoxNE: bbox.bBoxNE };
+        bbox.bBoxSW[0] -= amount / 2.0;
+        bbox.bBoxSW[1] -= amount / 2.0;
+        bbox.bBoxNE[0] += amount / 2.0;
+        bbox.bBoxNE[1] += amount / 2.0;
+        if let Some(anchors) = meta.glyphsWithAnchors.get_mut(name) {
+            for point in optical::anchor_points(anchors).iter_mut().filter_map(|p| p.as_mut()) {
+                *point = optical::refit(*point, &old, bbox);
+            }
+        }
+    }
+    changed
+}
+
+/// Make every line thickness `amount` stave spaces heavier, to keep lines
+/// drawn by ScoreFall matching glyphs made heavier by the same amount.
+pub fn adjust_engraving(engraving: &mut EngravingDefaults, amount: f32) {
+    for thickness in optical::thicknesses(engraving).iter_mut() {
+        **thickness = (**thickness + amount).max(0.0);
+    }
+}
+
+/// Move every edge of a path outwards by `offset` (inwards if negative).
+///
+/// Each point, including control points, is moved along the bisector of the
+/// normals of the lines to its neighbours, far enough that straight edges
+/// move by exactly `offset`.  Which side is outside is taken from the
+/// direction of the whole outline, so holes (which go the other way) shrink
+/// as their outer contour grows.  Arc radii are left as they are.
+pub fn embolden(path: &Path, offset: f64) -> Path {
+    let path = outline::simplify(path);
+
+    // Split into contours, each starting with a move.
+    let mut contours: Vec<Vec<PathSegment>> = Vec::new();
+    for segment in path.iter() {
+        match (segment, contours.last_mut()) {
+            (PathSegment::MoveTo { .. }, _) | (_, None) => contours.push(vec![*segment]),
+            (_, Some(contour)) => contour.push(*segment),
+        }
+    }
+
+    let points: Vec<Vec<(f64, f64)>> = contours
+        .iter()
+        .map(|contour| contour.iter().flat_map(points).collect())
+        .collect();
+    let area: f64 = points.iter().map(|p| signed_area(p)).sum();
+    if area == 0.0 {
+        return path;
+    }
+    let outside = area.signum();
+
+    let mut output = Path::with_capacity(path.len());
+    for (contour, points) in contours.iter().zip(points.iter()) {
+        let closed = contour.iter().any(|s| matches!(s, PathSegment::ClosePath { .. }));
+        let shifts = shifts(points, closed, outside * offset);
+        let mut moved = points.iter().zip(shifts.iter()).map(|(p, s)| (p.0 + s.0, p.1 + s.1));
+        for segment in contour.iter() {
+            output.push(with_points(segment, &mut moved));
+        }
+    }
+    output
+}
+
+/// Average stroke width of an outline: twice its filled area over the length
+/// of its contours, as for a long, thin stroke.  Curves are measured as eight
+/// straight pieces each.
+pub fn stroke_width(path: &Path) -> f64 {
+    use svgdom::PathSegment::*;
+
+    const PIECES: usize = 8;
+    let mut contours: Vec<Vec<(f64, f64)>> = Vec::new();
+    for segment in outline::cubic(path).iter() {
+        let current = contours.last().and_then(|c| c.last()).cloned().unwrap_or((0.0, 0.0));
+        match *segment {
+            MoveTo { x, y, .. } => contours.push(vec![(x, y)]),
+            LineTo { x, y, .. } => contours.last_mut().unwrap().push((x, y)),
+            CurveTo { x1, y1, x2, y2, x, y, .. } => {
+                let (x0, y0) = current;
+                let contour = contours.last_mut().unwrap();
+                for i in 1..=PIECES {
+                    let t = i as f64 / PIECES as f64;
+                    let u = 1.0 - t;
+                    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    contour.push((
+                        a * x0 + b * x1 + c * x2 + d * x,
+                        a * y0 + b * y1 + c * y2 + d * y,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let area: f64 = contours.iter().map(|c| signed_area(c)).sum();
+    let length: f64 = contours
+        .iter()
+        .flat_map(|c| (0..c.len()).map(move |i| distance(c[i], c[(i + 1) % c.len()])))
+        .sum();
+    if length == 0.0 {
+        return 0.0;
+    }
+    2.0 * area.abs() / length
+}
+
+/// How far to move each point of a contour.  `offset` is positive to move
+/// points to the right of the contour's direction.
+fn shifts(points: &[(f64, f64)], closed: bool, offset: f64) -> Vec<(f64, f64)> {
+    const EPSILON: f64 = 1e-6;
+
+    let n = points.len();
+    // A closed contour may end on its starting point; that point is moved
+    // the same as the start.
+    let end = if closed && n > 1 && distance(points[0], points[n - 1]) < EPSILON {
+        n - 1
+    } else {
+        n
+    };
+
+    // Direction along the contour between point `i` and the nearest
+    // different point before or after it.
+    let direction = |i: usize, forward: bool| -> Option<(f64, f64)> {
+        let mut j = i;
+        for _ in 1..end {
+            j = match (forward, closed) {
+                (true, true) => (j + 1) % end,
+                (false, true) => (j + end - 1) % end,
+                (true, false) if j + 1 < end => j + 1,
+                (false, false) if j > 0 => j - 1,
+                _ => return None,
+            };
+            let d = distance(points[i], points[j]);
+            if d >= EPSILON {
+                let (dx, dy) = (points[j].0 - points[i].0, points[j].1 - points[i].1);
+                return Some(if forward { (dx / d, dy / d) } else { (-dx / d, -dy / d) });
+            }
+        }
+        None
+    };
+    // Right-hand normal of a direction, with the y axis up.
+    let normal = |(dx, dy): (f64, f64)| (dy, -dx);
+
+    let mut shifts = Vec::with_capacity(n);
+    for i in 0..end {
+        let shift = match (direction(i, false), direction(i, true)) {
+            (Some(incoming), Some(outgoing)) => {
+                let (a, b) = (normal(incoming), normal(outgoing));
+                // Never move a sharp corner more than a few times `offset`.
+                let scale = offset / (1.0 + a.0 * b.0 + a.1 * b.1).max(0.25);
+                ((a.0 + b.0) * scale, (a.1 + b.1) * scale)
+            }
+            (Some(d), None) | (None, Some(d)) => {
+                let a = normal(d);
+                (a.0 * offset, a.1 * offset)
+            }
+            (None, None) => (0.0, 0.0),
+        };
+        shifts.push(shift);
+    }
+    if end < n {
+        shifts.push(shifts[0]);
+    }
+    shifts
+}
+
+/// Points of a segment of a simplified path, in order.
+fn points(segment: &PathSegment) -> Vec<(f64, f64)> {
+    use svgdom::PathSegment::*;
+
+    match *segment {
+        MoveTo { x, y, .. } | LineTo { x, y, .. } | EllipticalArc { x, y, .. } => vec![(x, y)],
+        CurveTo { x1, y1, x2, y2, x, y, .. } => vec![(x1, y1), (x2, y2), (x, y)],
+        Quadratic { x1, y1, x, y, .. } => vec![(x1, y1), (x, y)],
+        _ => Vec::new(),
+    }
+}
+
+/// Copy of a segment of a simplified path with its points taken from
+/// `points`.
+fn with_points<I>(segment: &PathSegment, points: &mut I) -> PathSegment
+where
+    I: Iterator<Item = (f64, f64)>,
+{
+    use svgdom::PathSegment::*;
+
+    let mut next = || points.next().unwrap();
+    match *segment {
+        MoveTo { abs, .. } => {
+            let (x, y) = next();
+            MoveTo { abs, x, y }
+        }
+        LineTo { abs, .. } => {
+            let (x, y) = next();
+            LineTo { abs, x, y }
+        }
+        CurveTo { abs, .. } => {
+            let (x1, y1) = next();
+            let (x2, y2) = next();
+            let (x, y) = next();
+            CurveTo { abs, x1, y1, x2, y2, x, y }
+        }
+        Quadratic { abs, .. } => {
+            let (x1, y1) = next();
+            let (x, y) = next();
+            Quadratic { abs, x1, y1, x, y }
+        }
+        EllipticalArc { abs, rx, ry, x_axis_rotation, large_arc, sweep, .. } => {
+            let (x, y) = next();
+            EllipticalArc { abs, rx, ry, x_axis_rotation, large_arc, sweep, x, y }
+        }
+        segment => segment,
+    }
+}
+
+/// Signed area of the polygon through `points`, positive if it goes
+/// counter-clockwise with the y axis up.
+fn signed_area(points: &[(f64, f64)]) -> f64 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::tests::{metadata_json, source};
+
+    /// A square ring 1.6 stave spaces across, with 0.4 stave space strokes.
+    const RING: &str = "M0 0L400 0L400 400L0 400ZM100 100L100 300L300 300L300 100Z";
+
+    #[test]
+    fn embolden_grows_outside_and_shrinks_holes() {
+        let ss = STAVE_SPACE as f64;
+        let font = source("base.svg", &[("uniE0A4", "", RING)], metadata_json(""));
+        let ring = &font.glyphs[&0xE0A4];
+        for offset in [0.1, -0.1].iter() {
+            let bold = embolden(ring, offset * ss);
+            let bounds = outline::bounds(&bold).unwrap();
+            let expected = [-offset, -offset, 1.6 + offset, 1.6 + offset];
+            for (a, b) in bounds.iter().zip(expected.iter()) {
+                assert!((a - b * ss).abs() < 1e-9, "{:?}", bounds);
+            }
+            let stroke = stroke_width(&bold);
+            assert!((stroke - (0.4 + 2.0 * offset) * ss).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn apply_moves_bounding_boxes_and_anchors() {
+        let names = GlyphNames::parse(
+            "glyphnames.json",
+            r#"{
+                "noteheadBlack": {"codepoint": "U+E0A4", "description": ""},
+                "noteheadHalf": {"codepoint": "U+E0A3", "description": ""}
+            }"#,
+        );
+        let meta = metadata_json(
+            r#""glyphBBoxes": {
+                "noteheadBlack": {"bBoxSW": [0, 0], "bBoxNE": [1.6, 1.6]},
+                "noteheadHalf": {"bBoxSW": [0, 0], "bBoxNE": [1.6, 1.6]}
+            },
+            "glyphsWithAnchors": {
+                "noteheadBlack": {"stemUpSE": [1.6, 0.8], "opticalCenter": [0.8, 0.8]},
+                "noteheadHalf": {"stemUpSE": [1.6, 0.8]}
+            }"#,
+        );
+        let glyphs = [
+            ("uniE0A4", "", RING),
+            ("uniE0A3", "", RING),
+            ("uniE0A4_uniE0A4", "", RING),
+            ("uniE0A4_uniE0A3", "", RING),
+        ];
+        let mut font = Layered::new(source("base.svg", &glyphs, meta));
+        assert_eq!(apply(&mut font, &names, Some(&[0xE0A4]), 0.2), 2);
+
+        // Only the ligature of emboldened glyphs is emboldened with them.
+        let extent = |path| outline::extent(path).unwrap();
+        let ring = extent(&font.glyphs[&0xE0A3]);
+        assert_eq!(extent(&font.ligatures[&vec![0xE0A4, 0xE0A4]]), extent(&font.glyphs[&0xE0A4]));
+        assert_ne!(extent(&font.glyphs[&0xE0A4]), ring);
+        assert_eq!(extent(&font.ligatures[&vec![0xE0A4, 0xE0A3]]), ring);
+
+        let meta = &font.metadata;
+        let bbox = &meta.glyphBBoxes["noteheadBlack"];
+        assert_eq!((bbox.bBoxSW, bbox.bBoxNE), ([-0.1, -0.1], [1.7, 1.7]));
+        let anchors = &meta.glyphsWithAnchors["noteheadBlack"];
+        let [x, y] = anchors.stemUpSE.unwrap();
+        assert!((x - 1.7).abs() < 1e-6 && (y - 0.8).abs() < 1e-6);
+        let [x, y] = anchors.opticalCenter.unwrap();
+        assert!((x - 0.8).abs() < 1e-6 && (y - 0.8).abs() < 1e-6);
+        assert_eq!(meta.glyphsWithAnchors["noteheadHalf"].stemUpSE, Some([1.6, 0.8]));
+        assert_eq!(meta.glyphBBoxes["noteheadHalf"].bBoxNE, [1.6, 1.6]);
+    }
+}